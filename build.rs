@@ -1,5 +1,17 @@
+#[cfg(feature = "napi")]
 extern crate napi_build;
 
 fn main() {
+  #[cfg(feature = "napi")]
   napi_build::setup();
+
+  // C APIのヘッダーをOUT_DIRに生成する (ソースツリーのinclude/emoji_js.hはcbindgenコマンドで更新し、CIで差分を確認する)
+  #[cfg(feature = "capi")]
+  {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    cbindgen::generate(&crate_dir)
+      .expect("Failed to generate C header")
+      .write_to_file(format!("{}/emoji_js.h", out_dir));
+  }
 }
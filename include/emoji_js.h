@@ -0,0 +1,136 @@
+#ifndef EMOJI_JS_H
+#define EMOJI_JS_H
+
+/* This file is generated by cbindgen. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of the C API functions. Each error corresponds to the error code of the same name in the Node.js binding.
+ */
+typedef enum EmojiStatus {
+  EMOJI_STATUS_OK = 0,
+  EMOJI_STATUS_INVALID_COLOR = 1,
+  EMOJI_STATUS_INVALID_TEXT_ALIGN = 2,
+  EMOJI_STATUS_INVALID_FORMAT = 3,
+  EMOJI_STATUS_INVALID_OPTION = 4,
+  EMOJI_STATUS_FONT_LOAD_FAILED = 5,
+  EMOJI_STATUS_SURFACE_CREATION_FAILED = 6,
+  EMOJI_STATUS_ENCODE_FAILED = 7,
+  EMOJI_STATUS_LIMITS_EXCEEDED = 8,
+  EMOJI_STATUS_IMAGE_DECODE_FAILED = 9,
+  EMOJI_STATUS_ALPHA_LOSS = 10,
+  /**
+   * A NULL pointer or a string which is not UTF-8 was passed, or no text was set
+   */
+  EMOJI_STATUS_INVALID_ARGUMENT = 100,
+  /**
+   * An unexpected internal error (panic) occurred
+   */
+  EMOJI_STATUS_INTERNAL_ERROR = 101,
+} EmojiStatus;
+
+/**
+ * Opaque generator handle created with `emoji_generator_new`.
+ */
+typedef struct EmojiGenerator EmojiGenerator;
+
+/**
+ * Rectangle cropped by the `trim` option, in the coordinates of the untrimmed image.
+ */
+typedef struct EmojiCropRect {
+  int32_t x;
+  int32_t y;
+  uint32_t width;
+  uint32_t height;
+} EmojiCropRect;
+
+/**
+ * Creates a generator with the default options. Free it with `emoji_generator_free`.
+ */
+struct EmojiGenerator *emoji_generator_new(void);
+
+/**
+ * Frees a generator. Does nothing if `generator` is NULL.
+ *
+ * # Safety
+ * `generator` must be NULL or a pointer returned by `emoji_generator_new` which has not been freed yet.
+ */
+void emoji_generator_free(struct EmojiGenerator *generator);
+
+/**
+ * Sets the text to render. Lines are separated by `\n`.
+ *
+ * # Safety
+ * `generator` must be a valid generator and `text` a NUL-terminated string.
+ */
+enum EmojiStatus emoji_generator_set_text(struct EmojiGenerator *generator, const char *text);
+
+/**
+ * Sets an option by its snake_case name (eg. `"color"`, `"#FF0000"`).
+ *
+ * Numbers and booleans (`true` / `false`) are passed as strings. `typeface_variation` and
+ * `opentype_feature` take `KEY=VALUE` and can be set repeatedly. `color_palette` takes colors separated by `;`.
+ * `fill_image` and `background_image` take image file paths. `overlay_image` takes `PATH@X,Y` or `PATH@X,Y,WxH`
+ * and can be set repeatedly to draw several images.
+ *
+ * # Safety
+ * `generator` must be a valid generator and `key` / `value` NUL-terminated strings.
+ */
+enum EmojiStatus emoji_generator_set_option(struct EmojiGenerator *generator,
+                                            const char *key,
+                                            const char *value);
+
+/**
+ * Generates the image. On success `*data` and `*length` receive the encoded image,
+ * which must be freed with `emoji_buffer_free`.
+ * Returns `EMOJI_STATUS_INVALID_ARGUMENT` if no text has been set with `emoji_generator_set_text`.
+ *
+ * # Safety
+ * `generator` must be a valid generator and `data` / `length` valid pointers.
+ */
+enum EmojiStatus emoji_generator_generate(struct EmojiGenerator *generator,
+                                          uint8_t **data,
+                                          size_t *length);
+
+/**
+ * Stores the rectangle cropped by the last successful `emoji_generator_generate` in `*rect`.
+ * Returns false (leaving `*rect` untouched) if `trim` is not set or no image has been generated.
+ *
+ * # Safety
+ * `generator` must be a valid generator and `rect` a valid pointer.
+ */
+bool emoji_generator_crop_rect(const struct EmojiGenerator *generator, struct EmojiCropRect *rect);
+
+/**
+ * Returns the warnings of the last successful `emoji_generator_generate` separated by `\n`
+ * (eg. with `alpha_loss` set to `warn`), or NULL if there are none.
+ * The string is valid until the next call with the same generator.
+ *
+ * # Safety
+ * `generator` must be a valid generator.
+ */
+const char *emoji_generator_warnings(const struct EmojiGenerator *generator);
+
+/**
+ * Returns the message of the last error on the generator, or NULL if no error has occurred.
+ * The string is valid until the next call with the same generator.
+ *
+ * # Safety
+ * `generator` must be a valid generator.
+ */
+const char *emoji_generator_last_error(const struct EmojiGenerator *generator);
+
+/**
+ * Frees an image returned by `emoji_generator_generate`. Does nothing if `data` is NULL.
+ *
+ * # Safety
+ * `data` and `length` must be the values returned by `emoji_generator_generate`, and freed only once.
+ */
+void emoji_buffer_free(uint8_t *data, size_t length);
+
+#endif /* EMOJI_JS_H */
@@ -0,0 +1,586 @@
+use clap::Parser;
+use emoji_js::{generate_with_info, Error, Options, OverlayImage, Output};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+// CSVのマニフェストで文字列として扱う列 (それ以外はJSONの値として解釈する)
+const STRING_COLUMNS: [&str; 21] = [
+    "text",
+    "output",
+    "color",
+    "fill_image_tile_mode",
+    "background_color",
+    "outline_color",
+    "shape",
+    "shape_color",
+    "shape_border_color",
+    "text_align",
+    "typeface_file",
+    "typeface_name",
+    "typeface_width",
+    "typeface_slant",
+    "letter_spacing",
+    "font_hinting",
+    "font_edging",
+    "format",
+    "matte_color",
+    "alpha_loss",
+    "trim",
+];
+
+/// Generate emoji images from text
+#[derive(Parser)]
+#[command(name = "emoji-gen", version)]
+struct Args {
+    /// Text to render ("\n" is a line break). Read from stdin if omitted
+    text: Option<String>,
+
+    /// Output file. Written to stdout if omitted or "-"
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// JSON or CSV manifest for batch mode. Each entry has `text`, `output` and any options
+    #[arg(short, long, conflicts_with_all = ["text", "output"])]
+    manifest: Option<String>,
+
+    #[arg(long)]
+    width: Option<u32>,
+    #[arg(long)]
+    height: Option<u32>,
+    #[arg(long)]
+    flexible_width: bool,
+    #[arg(long)]
+    color: Option<String>,
+    /// Palette used by `--color hash`. Can be repeated
+    #[arg(long)]
+    color_palette: Vec<String>,
+    /// Image file used as the fill of the text
+    #[arg(long)]
+    fill_image: Option<String>,
+    #[arg(long, value_parser = ["repeat", "mirror", "clamp", "decal"])]
+    fill_image_tile_mode: Option<String>,
+    #[arg(long)]
+    fill_image_scale: Option<f32>,
+    #[arg(long)]
+    background_color: Option<String>,
+    #[arg(long, value_parser = ["left", "center", "right"])]
+    text_align: Option<String>,
+    #[arg(long)]
+    text_size_fixed: bool,
+    #[arg(long)]
+    disable_stretch: bool,
+    #[arg(long)]
+    disable_outline: bool,
+    #[arg(long)]
+    outline_width: Option<u32>,
+    #[arg(long)]
+    outline_color: Option<String>,
+    #[arg(long, value_parser = ["rect", "circle", "rounded_rect", "pill", "squircle", "speech_bubble"])]
+    shape: Option<String>,
+    #[arg(long)]
+    shape_color: Option<String>,
+    #[arg(long)]
+    shape_border_width: Option<u32>,
+    #[arg(long)]
+    shape_border_color: Option<String>,
+    #[arg(long)]
+    shape_radius: Option<u32>,
+    #[arg(long)]
+    shape_padding: Option<u32>,
+    /// Image file drawn behind the text
+    #[arg(long)]
+    background_image: Option<String>,
+    #[arg(long, value_parser = ["cover", "contain", "stretch", "tile"])]
+    background_image_fit: Option<String>,
+    /// Image file drawn on top of the text at X,Y (optionally resized to WxH). Can be repeated
+    #[arg(long, value_name = "PATH@X,Y[,WxH]", value_parser = parse_overlay)]
+    overlay_image: Vec<OverlayArg>,
+    #[arg(long, value_parser = ["none", "bounds", "keep_height", "keep_aspect"])]
+    trim: Option<String>,
+    #[arg(long)]
+    trim_padding: Option<u32>,
+    #[arg(long)]
+    typeface_file: Option<String>,
+    #[arg(long)]
+    typeface_index: Option<u32>,
+    #[arg(long)]
+    typeface_name: Option<String>,
+    #[arg(long)]
+    typeface_weight: Option<u32>,
+    #[arg(long)]
+    typeface_width: Option<String>,
+    #[arg(long)]
+    typeface_slant: Option<String>,
+    /// Variation axis value (eg. wght=900). Can be repeated
+    #[arg(long, value_name = "AXIS=VALUE", value_parser = parse_key_value::<f32>)]
+    typeface_variation: Vec<(String, f32)>,
+    #[arg(long)]
+    fit_width_axis: bool,
+    /// OpenType feature value (eg. palt=1). Can be repeated
+    #[arg(long, value_name = "TAG=VALUE", value_parser = parse_key_value::<u32>)]
+    opentype_feature: Vec<(String, u32)>,
+    #[arg(long, allow_hyphen_values = true)]
+    letter_spacing: Option<String>,
+    #[arg(long)]
+    fit_precision: Option<f32>,
+    #[arg(long, value_parser = ["none", "slight", "normal", "full"])]
+    font_hinting: Option<String>,
+    #[arg(long, value_parser = ["alias", "antialias", "subpixel_antialias"])]
+    font_edging: Option<String>,
+    #[arg(long)]
+    font_subpixel: bool,
+    /// Render at N times the resolution and downsample (1 to 8)
+    #[arg(long)]
+    supersample: Option<u32>,
+    /// Disable antialiasing and align glyphs to whole pixels
+    #[arg(long)]
+    pixel_art: bool,
+    /// Limit the image to this many colors (2 to 256)
+    #[arg(long)]
+    palette_size: Option<u32>,
+    /// Lower the quality until the encoded image fits in this many bytes
+    #[arg(long)]
+    max_bytes: Option<u32>,
+    #[arg(long, value_parser = ["png", "jpeg"])]
+    format: Option<String>,
+    #[arg(long)]
+    matte_color: Option<String>,
+    #[arg(long, value_parser = ["ignore", "warn", "error"])]
+    alpha_loss: Option<String>,
+    #[arg(long)]
+    quality: Option<u32>,
+}
+
+impl Args {
+    fn options(&self) -> Result<Options, String> {
+        let background_image = read_file(self.background_image.as_deref());
+        if background_image.is_err() {
+            return Err(background_image.unwrap_err());
+        }
+
+        let fill_image = read_file(self.fill_image.as_deref());
+        if fill_image.is_err() {
+            return Err(fill_image.unwrap_err());
+        }
+
+        let mut overlay_images = Vec::new();
+        for overlay in &self.overlay_image {
+            let image = read_file(Some(overlay.path.as_str()));
+            if image.is_err() {
+                return Err(image.unwrap_err());
+            }
+
+            overlay_images.push(OverlayImage {
+                image: image.unwrap().unwrap(),
+                x: overlay.x,
+                y: overlay.y,
+                width: overlay.width,
+                height: overlay.height,
+            });
+        }
+
+        return Ok(Options {
+            width: self.width,
+            height: self.height,
+            flexible_width: flag(self.flexible_width),
+            color: self.color.clone(),
+            color_palette: if self.color_palette.is_empty() { None } else { Some(self.color_palette.clone()) },
+            fill_image: fill_image.unwrap(),
+            fill_image_tile_mode: self.fill_image_tile_mode.clone(),
+            fill_image_scale: self.fill_image_scale,
+            background_color: self.background_color.clone(),
+            text_align: self.text_align.clone(),
+            text_size_fixed: flag(self.text_size_fixed),
+            disable_stretch: flag(self.disable_stretch),
+            disable_outline: flag(self.disable_outline),
+            outline_width: self.outline_width,
+            outline_color: self.outline_color.clone(),
+            shape: self.shape.clone(),
+            shape_color: self.shape_color.clone(),
+            shape_border_width: self.shape_border_width,
+            shape_border_color: self.shape_border_color.clone(),
+            shape_radius: self.shape_radius,
+            shape_padding: self.shape_padding,
+            background_image: background_image.unwrap(),
+            background_image_fit: self.background_image_fit.clone(),
+            overlay_images: if overlay_images.is_empty() { None } else { Some(overlay_images) },
+            trim: self.trim.clone(),
+            trim_padding: self.trim_padding,
+            typeface_file: self.typeface_file.clone(),
+            typeface_index: self.typeface_index,
+            typeface_name: self.typeface_name.clone(),
+            typeface_weight: self.typeface_weight,
+            typeface_width: self.typeface_width.clone(),
+            typeface_slant: self.typeface_slant.clone(),
+            typeface_variations: collect(&self.typeface_variation),
+            fit_width_axis: flag(self.fit_width_axis),
+            opentype_features: collect(&self.opentype_feature),
+            letter_spacing: self.letter_spacing.clone(),
+            fit_precision: self.fit_precision,
+            font_hinting: self.font_hinting.clone(),
+            font_edging: self.font_edging.clone(),
+            font_subpixel: flag(self.font_subpixel),
+            supersample: self.supersample,
+            pixel_art: flag(self.pixel_art),
+            palette_size: self.palette_size,
+            max_bytes: self.max_bytes,
+            format: self.format.clone(),
+            matte_color: self.matte_color.clone(),
+            alpha_loss: self.alpha_loss.clone(),
+            quality: self.quality,
+        });
+    }
+}
+
+#[derive(Clone)]
+struct OverlayArg {
+    path: String,
+    x: i32,
+    y: i32,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+struct ManifestEntry {
+    text: String,
+    output: String,
+    options: Options,
+}
+
+fn read_file(path: Option<&str>) -> Result<Option<Vec<u8>>, String> {
+    if path.is_none() {
+        return Ok(None);
+    }
+
+    let path = path.unwrap();
+    let data = fs::read(path);
+    if data.is_err() {
+        return Err(format!("Failed to read {}: {}", path, data.unwrap_err()));
+    }
+
+    return Ok(Some(data.unwrap()));
+}
+
+// max_bytesに収めるために行った調整と警告を知らせる
+fn report(name: &str, output: &Output) {
+    if !output.adjustments.is_empty() {
+        eprintln!("{}: adjusted to fit max_bytes: {}", name, output.adjustments.join(", "));
+    }
+    for warning in &output.warnings {
+        eprintln!("{}: warning: {}", name, warning);
+    }
+}
+
+fn flag(value: bool) -> Option<bool> {
+    if value { Some(true) } else { None }
+}
+
+fn collect<T: Copy>(pairs: &[(String, T)]) -> Option<HashMap<String, T>> {
+    if pairs.is_empty() {
+        return None;
+    }
+
+    return Some(pairs.iter().cloned().collect());
+}
+
+fn parse_key_value<T: FromStr>(value: &str) -> Result<(String, T), String> {
+    let pair = value.split_once('=');
+    if pair.is_none() {
+        return Err(format!("expected KEY=VALUE: {}", value));
+    }
+
+    let (key, value) = pair.unwrap();
+    let parsed = value.parse::<T>();
+    if parsed.is_err() {
+        return Err(format!("invalid value: {}", value));
+    }
+
+    return Ok((key.to_string(), parsed.unwrap()));
+}
+
+// PATH@X,Y[,WxH] を解釈する (パスに@を含められるよう最後の@で分割する)
+fn parse_overlay(value: &str) -> Result<OverlayArg, String> {
+    let pair = value.rsplit_once('@');
+    if pair.is_none() {
+        return Err(format!("expected PATH@X,Y[,WxH]: {}", value));
+    }
+
+    let (path, geometry) = pair.unwrap();
+    let parts: Vec<&str> = geometry.split(',').collect();
+    if path.is_empty() || parts.len() < 2 || parts.len() > 3 {
+        return Err(format!("expected PATH@X,Y[,WxH]: {}", value));
+    }
+
+    let x = parts[0].parse::<i32>();
+    let y = parts[1].parse::<i32>();
+    if x.is_err() || y.is_err() {
+        return Err(format!("invalid position: {}", geometry));
+    }
+
+    let mut overlay = OverlayArg { path: path.to_string(), x: x.unwrap(), y: y.unwrap(), width: None, height: None };
+    if parts.len() == 3 {
+        let size = parts[2].split_once('x');
+        if size.is_none() {
+            return Err(format!("invalid size: {}", parts[2]));
+        }
+
+        let (width, height) = size.unwrap();
+        let width = width.parse::<u32>();
+        let height = height.parse::<u32>();
+        if width.is_err() || height.is_err() {
+            return Err(format!("invalid size: {}", parts[2]));
+        }
+        overlay.width = Some(width.unwrap());
+        overlay.height = Some(height.unwrap());
+    }
+
+    return Ok(overlay);
+}
+
+// マニフェストの画像の項目に文字列が指定されていれば、ファイルのパスとして読み込んでバイト列に置き換える
+fn resolve_image(value: &mut Value) -> Result<(), String> {
+    if let Value::String(path) = value {
+        let data = read_file(Some(path.as_str()));
+        if data.is_err() {
+            return Err(data.unwrap_err());
+        }
+        *value = Value::from(data.unwrap().unwrap());
+    }
+
+    return Ok(());
+}
+
+fn resolve_images(entry: &mut Value) -> Result<(), String> {
+    let entry = entry.as_object_mut();
+    if entry.is_none() {
+        return Ok(());
+    }
+
+    let entry = entry.unwrap();
+    for key in ["fill_image", "background_image"] {
+        if let Some(value) = entry.get_mut(key) {
+            let result = resolve_image(value);
+            if result.is_err() {
+                return result;
+            }
+        }
+    }
+
+    if let Some(Value::Array(overlays)) = entry.get_mut("overlay_images") {
+        for overlay in overlays {
+            if let Some(image) = overlay.get_mut("image") {
+                let result = resolve_image(image);
+                if result.is_err() {
+                    return result;
+                }
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+fn describe(error: Error) -> String {
+    return format!("{} ({})", error, error.code());
+}
+
+// シェルから"\n"を改行として渡せるようにする
+fn unescape_text(text: &str) -> String {
+    return text.replace("\\n", "\n");
+}
+
+fn read_manifest(path: &str) -> Result<Vec<ManifestEntry>, String> {
+    let content = fs::read_to_string(path);
+    if content.is_err() {
+        return Err(format!("Failed to read manifest: {}", path));
+    }
+
+    let content = content.unwrap();
+    let is_csv = Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let rows = if is_csv { read_csv_rows(&content) } else { read_json_rows(&content) };
+    if rows.is_err() {
+        return Err(rows.unwrap_err());
+    }
+
+    let mut entries = Vec::new();
+    for (line, mut row) in rows.unwrap().into_iter().enumerate() {
+        let result = resolve_images(&mut row);
+        if result.is_err() {
+            return Err(format!("Invalid manifest (row {}): {}", line + 1, result.unwrap_err()));
+        }
+
+        let entry = manifest_entry(row);
+        if entry.is_err() {
+            return Err(format!("Invalid manifest (row {}): {}", line + 1, entry.unwrap_err()));
+        }
+        entries.push(entry.unwrap());
+    }
+
+    return Ok(entries);
+}
+
+// textとoutputを取り出し、残りをオプションとして解釈する (未知の項目はエラーになる)
+fn manifest_entry(row: Value) -> Result<ManifestEntry, String> {
+    let mut row = match row {
+        Value::Object(row) => row,
+        _ => return Err("entry must be an object".to_string()),
+    };
+
+    let text = match row.remove("text") {
+        Some(Value::String(text)) => text,
+        _ => return Err("text must be a string".to_string()),
+    };
+    let output = match row.remove("output") {
+        Some(Value::String(output)) => output,
+        _ => return Err("output must be a string".to_string()),
+    };
+
+    let options = serde_json::from_value::<Options>(Value::Object(row));
+    if options.is_err() {
+        return Err(options.unwrap_err().to_string());
+    }
+
+    return Ok(ManifestEntry { text, output, options: options.unwrap() });
+}
+
+fn read_json_rows(content: &str) -> Result<Vec<Value>, String> {
+    let rows = serde_json::from_str::<Vec<Value>>(content);
+    if rows.is_err() {
+        return Err(format!("Invalid manifest: {}", rows.unwrap_err()));
+    }
+
+    return Ok(rows.unwrap());
+}
+
+fn read_csv_rows(content: &str) -> Result<Vec<Value>, String> {
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    let headers = reader.headers();
+    if headers.is_err() {
+        return Err(format!("Invalid manifest: {}", headers.unwrap_err()));
+    }
+
+    let headers = headers.unwrap().clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        if record.is_err() {
+            return Err(format!("Invalid manifest: {}", record.unwrap_err()));
+        }
+
+        // 空欄は未指定として扱う
+        let mut entry = serde_json::Map::new();
+        for (key, cell) in headers.iter().zip(record.unwrap().iter()) {
+            if cell.is_empty() {
+                continue;
+            }
+
+            let value = if STRING_COLUMNS.contains(&key) {
+                Value::String(unescape_text(cell))
+            } else {
+                serde_json::from_str(cell).unwrap_or_else(|_| Value::String(cell.to_string()))
+            };
+            entry.insert(key.to_string(), value);
+        }
+
+        rows.push(Value::Object(entry));
+    }
+
+    return Ok(rows);
+}
+
+fn write_output(output: Option<&str>, data: &[u8]) -> Result<(), String> {
+    match output {
+        None | Some("-") => {
+            let result = io::stdout().lock().write_all(data);
+            if result.is_err() {
+                return Err(format!("Failed to write to stdout: {}", result.unwrap_err()));
+            }
+        },
+        Some(path) => {
+            let result = fs::write(path, data);
+            if result.is_err() {
+                return Err(format!("Failed to write {}: {}", path, result.unwrap_err()));
+            }
+        },
+    }
+
+    return Ok(());
+}
+
+fn run_single(args: &Args, options: Options) -> Result<(), String> {
+    let text = match &args.text {
+        Some(text) => unescape_text(text),
+        None => {
+            let mut text = String::new();
+            let result = io::stdin().read_to_string(&mut text);
+            if result.is_err() {
+                return Err(format!("Failed to read stdin: {}", result.unwrap_err()));
+            }
+            text.trim_end_matches(['\r', '\n']).to_string()
+        },
+    };
+
+    let result = generate_with_info(text, options);
+    if result.is_err() {
+        return Err(describe(result.unwrap_err()));
+    }
+
+    let output = result.unwrap();
+    report("emoji-gen", &output);
+    return write_output(args.output.as_deref(), &output.data);
+}
+
+fn run_batch(manifest: &str, options: Options) -> Result<(), String> {
+    let entries = read_manifest(manifest);
+    if entries.is_err() {
+        return Err(entries.unwrap_err());
+    }
+
+    // エントリごとのエラーは報告して続行し、最後にまとめて失敗を返す
+    let mut failed = 0;
+    for entry in entries.unwrap() {
+        let result = generate_with_info(entry.text, options.clone().merge(entry.options));
+        let result = match result {
+            Ok(output) => {
+                report(&entry.output, &output);
+                write_output(Some(&entry.output), &output.data)
+            },
+            Err(error) => Err(describe(error)),
+        };
+
+        if result.is_err() {
+            eprintln!("{}: {}", entry.output, result.unwrap_err());
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of the entries failed", failed));
+    }
+
+    return Ok(());
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let options = args.options();
+    let result = match options {
+        Ok(options) => match &args.manifest {
+            Some(manifest) => run_batch(manifest, options),
+            None => run_single(&args, options),
+        },
+        Err(error) => Err(error),
+    };
+
+    if result.is_err() {
+        eprintln!("emoji-gen: {}", result.unwrap_err());
+        return ExitCode::FAILURE;
+    }
+
+    return ExitCode::SUCCESS;
+}
@@ -0,0 +1,432 @@
+use clap::Parser;
+use emoji_js::{register_typeface_file, Error, Generator, Options};
+use std::collections::HashMap;
+use std::fs;
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Serve emoji images over HTTP (compatible with emoji-gen's `GET /emoji` API)
+#[derive(Parser)]
+#[command(name = "emoji-server", version)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    bind: String,
+
+    /// Number of images generated at the same time
+    #[arg(long)]
+    concurrency: Option<usize>,
+
+    /// Number of requests waiting for a worker before responding with 503
+    #[arg(long, default_value_t = 64)]
+    queue_size: usize,
+
+    /// Max age of the Cache-Control header in seconds
+    #[arg(long, default_value_t = 86400)]
+    max_age: u32,
+
+    /// Maximum number of pixels of an image (width x height, multiplied by supersample squared)
+    #[arg(long, default_value_t = 1024 * 1024)]
+    max_pixels: u64,
+
+    /// Register a font file usable with the `font` parameter (eg. noto=/path/to/font.ttc#0). Can be repeated
+    #[arg(long, value_name = "ALIAS=PATH[#INDEX]")]
+    font: Vec<String>,
+}
+
+// widthとheightを指定しなかった場合の大きさ (Generatorの既定値)
+const DEFAULT_SIZE: u64 = 128;
+
+#[derive(Clone, Copy)]
+struct Settings {
+    max_age: u32,
+    max_pixels: u64,
+    font_set: u64,
+}
+
+struct Query {
+    text: Option<String>,
+    options: Options,
+}
+
+// 検証を終えてワーカーに渡すリクエスト
+struct Job {
+    request: Request,
+    text: String,
+    options: Options,
+    tag: String,
+}
+
+fn header(name: &str, value: &str) -> Header {
+    return Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap();
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(format!("{} must be true or false: {}", key, value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    let parsed = value.parse::<T>();
+    if parsed.is_err() {
+        return Err(format!("{} must be a number: {}", key, value));
+    }
+
+    return Ok(parsed.unwrap());
+}
+
+fn parse_pair<T: std::str::FromStr>(key: &str, value: &str) -> Result<(String, T), String> {
+    let pair = value.split_once('=');
+    if pair.is_none() {
+        return Err(format!("{} must be KEY=VALUE: {}", key, value));
+    }
+
+    let (name, value) = pair.unwrap();
+    let parsed = parse_number::<T>(key, value);
+    if parsed.is_err() {
+        return Err(parsed.err().unwrap());
+    }
+
+    return Ok((name.to_string(), parsed.unwrap()));
+}
+
+// クエリパラメータをオプションに変換する (emoji-genの名前も受け付ける)
+fn parse_query(query: &str) -> Result<Query, String> {
+    let mut text = None;
+    let mut options = Options::default();
+    let mut variations = HashMap::new();
+    let mut features = HashMap::new();
+    let mut palette = Vec::new();
+
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        let value = value.into_owned();
+        match key.as_ref() {
+            "text" => text = Some(value),
+            "width" => options.width = Some(parse_number("width", &value)?),
+            "height" => options.height = Some(parse_number("height", &value)?),
+            "flexible_width" => options.flexible_width = Some(parse_bool("flexible_width", &value)?),
+            "color" => options.color = Some(value),
+            "color_palette" => palette.push(value),
+            "background_color" | "back_color" => options.background_color = Some(value),
+            "text_align" | "align" => options.text_align = Some(value),
+            "text_size_fixed" | "size_fixed" => options.text_size_fixed = Some(parse_bool("text_size_fixed", &value)?),
+            "disable_stretch" => options.disable_stretch = Some(parse_bool("disable_stretch", &value)?),
+            "stretch" => options.disable_stretch = Some(!parse_bool("stretch", &value)?),
+            "disable_outline" => options.disable_outline = Some(parse_bool("disable_outline", &value)?),
+            "outline_width" => options.outline_width = Some(parse_number("outline_width", &value)?),
+            "outline_color" => options.outline_color = Some(value),
+            "shape" => options.shape = Some(value),
+            "shape_color" => options.shape_color = Some(value),
+            "shape_border_width" => options.shape_border_width = Some(parse_number("shape_border_width", &value)?),
+            "shape_border_color" => options.shape_border_color = Some(value),
+            "shape_radius" => options.shape_radius = Some(parse_number("shape_radius", &value)?),
+            "shape_padding" => options.shape_padding = Some(parse_number("shape_padding", &value)?),
+            "trim" => options.trim = Some(value),
+            "trim_padding" => options.trim_padding = Some(parse_number("trim_padding", &value)?),
+            "typeface_name" | "font" => options.typeface_name = Some(value),
+            "typeface_weight" => options.typeface_weight = Some(parse_number("typeface_weight", &value)?),
+            "typeface_width" => options.typeface_width = Some(value),
+            "typeface_slant" => options.typeface_slant = Some(value),
+            "typeface_variation" => {
+                let (axis, value) = parse_pair::<f32>("typeface_variation", &value)?;
+                variations.insert(axis, value);
+            },
+            "fit_width_axis" => options.fit_width_axis = Some(parse_bool("fit_width_axis", &value)?),
+            "opentype_feature" => {
+                let (tag, value) = parse_pair::<u32>("opentype_feature", &value)?;
+                features.insert(tag, value);
+            },
+            "letter_spacing" => options.letter_spacing = Some(value),
+            "fit_precision" => options.fit_precision = Some(parse_number("fit_precision", &value)?),
+            "font_hinting" => options.font_hinting = Some(value),
+            "font_edging" => options.font_edging = Some(value),
+            "font_subpixel" => options.font_subpixel = Some(parse_bool("font_subpixel", &value)?),
+            "supersample" => options.supersample = Some(parse_number("supersample", &value)?),
+            "pixel_art" => options.pixel_art = Some(parse_bool("pixel_art", &value)?),
+            "palette_size" => options.palette_size = Some(parse_number("palette_size", &value)?),
+            "max_bytes" => options.max_bytes = Some(parse_number("max_bytes", &value)?),
+            "format" => options.format = Some(value),
+            "matte_color" => options.matte_color = Some(value),
+            "alpha_loss" => options.alpha_loss = Some(value),
+            "quality" => options.quality = Some(parse_number("quality", &value)?),
+            // emoji-genのAPIとの互換のために受け付けるが使用しない
+            "locale" | "public_fg" => {},
+            // サーバー上の任意のファイルを読めてしまうため、typeface_fileは受け付けない
+            _ => return Err(format!("Unknown parameter: {}", key)),
+        }
+    }
+
+    if !variations.is_empty() {
+        options.typeface_variations = Some(variations);
+    }
+    if !features.is_empty() {
+        options.opentype_features = Some(features);
+    }
+    if !palette.is_empty() {
+        options.color_palette = Some(palette);
+    }
+
+    return Ok(Query { text, options });
+}
+
+// 64bitのFNV-1a。再起動やRustのバージョンでETagが変わらないよう標準のHasherは使わない
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    let mut hash = hash;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001B3);
+    }
+
+    return hash;
+}
+
+// 登録したフォントの組のハッシュ。--font の指定に加えて起動時のファイルの大きさと更新日時を含め、
+// 同じパスのフォントを差し替えて再起動した場合も別のETagになるようにする
+fn font_set_hash(fonts: &[String]) -> u64 {
+    let mut fonts = fonts.to_vec();
+    fonts.sort();
+
+    let mut hash = 0xCBF29CE484222325;
+    for font in &fonts {
+        hash = fnv1a(hash, font.as_bytes());
+        hash = fnv1a(hash, &[0]);
+
+        let metadata = parse_font(font).ok().and_then(|(_, path, _)| fs::metadata(path).ok());
+        if let Some(metadata) = metadata {
+            hash = fnv1a(hash, &metadata.len().to_be_bytes());
+            let modified = metadata.modified().ok().and_then(|modified| modified.duration_since(UNIX_EPOCH).ok()).unwrap_or_default();
+            hash = fnv1a(hash, &modified.as_nanos().to_be_bytes());
+        }
+    }
+
+    return hash;
+}
+
+// 同じクエリからは同じ画像が生成されるため、パラメータの順序に依存しないハッシュをETagにする
+// (バージョンと登録したフォントの組も含める)
+fn etag(query: &str, font_set: u64) -> String {
+    let mut pairs: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+    pairs.sort();
+
+    let mut hash = fnv1a(0xCBF29CE484222325, env!("CARGO_PKG_VERSION").as_bytes());
+    hash = fnv1a(hash, &font_set.to_be_bytes());
+    for (key, value) in &pairs {
+        // 区切りのNULで "a=bc" と "ab=c" のような組を区別する
+        hash = fnv1a(hash, &[0]);
+        hash = fnv1a(hash, key.as_bytes());
+        hash = fnv1a(hash, &[0]);
+        hash = fnv1a(hash, value.as_bytes());
+    }
+
+    return format!("\"{:016x}\"", hash);
+}
+
+fn error_response(status: u16, code: &str, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::json!({ "code": code, "message": message }).to_string();
+    return Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"));
+}
+
+// 画像の画素数が上限を超えないか確かめる (幅可変モードの幅は生成時にGeneratorで確かめる)
+fn check_size(options: &Options, max_pixels: u64) -> Result<(), String> {
+    let width = options.width.map_or(DEFAULT_SIZE, |width| width as u64);
+    let height = options.height.map_or(DEFAULT_SIZE, |height| height as u64);
+    let scale = options.supersample.unwrap_or(1) as u64;
+    let pixels = width.saturating_mul(height).saturating_mul(scale).saturating_mul(scale);
+    if pixels > max_pixels {
+        return Err(format!("Image must be {} pixels or less: {}x{} (supersample: {})", max_pixels, width, height, scale));
+    }
+
+    return Ok(());
+}
+
+// リクエストを検証し、304やエラーはその場で返す (生成が必要なものだけをワーカーに渡す)
+fn prepare(request: Request, settings: Settings) -> Option<Job> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    if path != "/emoji" {
+        let _ = request.respond(error_response(404, "NotFound", "Not found."));
+        return None;
+    }
+    if request.method() != &Method::Get && request.method() != &Method::Head {
+        let response = error_response(405, "MethodNotAllowed", "Method not allowed.").with_header(header("Allow", "GET, HEAD"));
+        let _ = request.respond(response);
+        return None;
+    }
+
+    let tag = etag(query, settings.font_set);
+    let parsed = parse_query(query);
+    if parsed.is_err() {
+        let _ = request.respond(error_response(400, "InvalidOption", &parsed.err().unwrap()));
+        return None;
+    }
+
+    let parsed = parsed.unwrap();
+    if parsed.text.is_none() {
+        let _ = request.respond(error_response(400, "InvalidOption", "text is required."));
+        return None;
+    }
+
+    let result = check_size(&parsed.options, settings.max_pixels);
+    if result.is_err() {
+        let _ = request.respond(error_response(400, "LimitsExceeded", &result.unwrap_err()));
+        return None;
+    }
+
+    // 不正なクエリに304を返さないよう、ETagの比較は検証の後に行う
+    let not_modified = request.headers().iter()
+        .filter(|h| h.field.equiv("If-None-Match"))
+        .any(|h| h.value.as_str().split(',').any(|value| value.trim() == tag || value.trim() == "*"));
+    if not_modified {
+        let response = Response::empty(304)
+            .with_header(header("ETag", &tag))
+            .with_header(header("Cache-Control", &format!("public, max-age={}", settings.max_age)));
+        let _ = request.respond(response);
+        return None;
+    }
+
+    return Some(Job { request, text: parsed.text.unwrap(), options: parsed.options, tag });
+}
+
+fn handle(job: Job, settings: Settings) {
+    let Job { request, text, options, tag } = job;
+
+    let mut emoji = Generator::new();
+    emoji.set_texts(text);
+    emoji.set_max_pixels(settings.max_pixels);
+
+    let result = emoji.set_options(options);
+    let result = if result.is_err() { Err(result.unwrap_err()) } else { emoji.generate() };
+    if result.is_err() {
+        let error: Error = result.unwrap_err();
+        let status = match error {
+            Error::SurfaceCreationFailed | Error::EncodeFailed => 500,
+            _ => 400,
+        };
+        let _ = request.respond(error_response(status, error.code(), &error.to_string()));
+        return;
+    }
+
+    // max_bytesに収めるためにformatから変わることがあるため、実際の形式を返す
+    let format = emoji.get_output_format();
+    let mut response = Response::from_data(result.unwrap().as_bytes().to_vec())
+        .with_header(header("Content-Type", &format!("image/{}", format)))
+        .with_header(header("ETag", &tag))
+        .with_header(header("Cache-Control", &format!("public, max-age={}", settings.max_age)));
+
+    // trimで切り取った範囲を x,y,width,height で返す
+    if let Some(rect) = emoji.get_crop_rect() {
+        response.add_header(header("X-Crop-Rect", &format!("{},{},{},{}", rect.x, rect.y, rect.width, rect.height)));
+    }
+    if !emoji.get_adjustments().is_empty() {
+        response.add_header(header("X-Adjustments", &emoji.get_adjustments().join(",")));
+    }
+    for warning in emoji.get_warnings() {
+        response.add_header(header("X-Warning", warning));
+    }
+    let _ = request.respond(response);
+}
+
+fn worker(receiver: Arc<Mutex<Receiver<Job>>>, settings: Settings) {
+    loop {
+        let job = receiver.lock().unwrap().recv();
+        if job.is_err() {
+            return;
+        }
+        handle(job.unwrap(), settings);
+    }
+}
+
+// ALIAS=PATH[#INDEX] を別名・パス・インデックスに分ける
+fn parse_font(font: &str) -> Result<(&str, &str, usize), String> {
+    let pair = font.split_once('=');
+    if pair.is_none() {
+        return Err(format!("--font must be ALIAS=PATH[#INDEX]: {}", font));
+    }
+
+    let (alias, path) = pair.unwrap();
+    let (path, index) = match path.rsplit_once('#') {
+        Some((path, index)) if index.parse::<usize>().is_ok() => (path, index.parse::<usize>().unwrap()),
+        _ => (path, 0),
+    };
+
+    return Ok((alias, path, index));
+}
+
+fn register_fonts(fonts: &[String]) -> Result<(), String> {
+    for font in fonts {
+        let parsed = parse_font(font);
+        if parsed.is_err() {
+            return Err(parsed.unwrap_err());
+        }
+
+        let (alias, path, index) = parsed.unwrap();
+        let result = register_typeface_file(alias.to_string(), path.to_string(), index);
+        if result.is_err() {
+            return Err(result.unwrap_err().to_string());
+        }
+    }
+
+    return Ok(());
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let result = register_fonts(&args.font);
+    if result.is_err() {
+        eprintln!("emoji-server: {}", result.unwrap_err());
+        return ExitCode::FAILURE;
+    }
+
+    let server = Server::http(&args.bind);
+    if server.is_err() {
+        eprintln!("emoji-server: Failed to listen on {}: {}", args.bind, server.err().unwrap());
+        return ExitCode::FAILURE;
+    }
+
+    let server = server.unwrap();
+    let concurrency = args.concurrency
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+        .max(1);
+
+    // 生成はCPUを占有するため、同時に処理する数をワーカーの数に制限し、溢れた分は503で断る
+    let (sender, receiver): (SyncSender<Job>, Receiver<Job>) = mpsc::sync_channel(args.queue_size);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let settings = Settings {
+        max_age: args.max_age,
+        max_pixels: args.max_pixels,
+        font_set: font_set_hash(&args.font),
+    };
+    for _ in 0..concurrency {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || worker(receiver, settings));
+    }
+
+    eprintln!("emoji-server: Listening on http://{}", args.bind);
+    for request in server.incoming_requests() {
+        let job = prepare(request, settings);
+        if job.is_none() {
+            continue;
+        }
+
+        match sender.try_send(job.unwrap()) {
+            Ok(()) => {},
+            Err(TrySendError::Full(job)) | Err(TrySendError::Disconnected(job)) => {
+                let response = error_response(503, "ServerBusy", "Server is busy.").with_header(header("Retry-After", "1"));
+                let _ = job.request.respond(response);
+            },
+        }
+    }
+
+    return ExitCode::SUCCESS;
+}
@@ -0,0 +1,436 @@
+use crate::emoji::{Error, Generator, Options, OverlayImage};
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Result of the C API functions. Each error corresponds to the error code of the same name in the Node.js binding.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmojiStatus {
+    Ok = 0,
+    InvalidColor = 1,
+    InvalidTextAlign = 2,
+    InvalidFormat = 3,
+    InvalidOption = 4,
+    FontLoadFailed = 5,
+    SurfaceCreationFailed = 6,
+    EncodeFailed = 7,
+    LimitsExceeded = 8,
+    ImageDecodeFailed = 9,
+    AlphaLoss = 10,
+    /// A NULL pointer or a string which is not UTF-8 was passed, or no text was set
+    InvalidArgument = 100,
+    /// An unexpected internal error (panic) occurred
+    InternalError = 101,
+}
+
+/// Rectangle cropped by the `trim` option, in the coordinates of the untrimmed image.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmojiCropRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Opaque generator handle created with `emoji_generator_new`.
+pub struct EmojiGenerator {
+    generator: Generator,
+    typeface_file: Option<String>,
+    typeface_index: u32,
+    last_error: Option<CString>,
+    warnings: Option<CString>,
+    has_text: bool,
+}
+
+impl From<&Error> for EmojiStatus {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::InvalidColor(_) => EmojiStatus::InvalidColor,
+            Error::InvalidTextAlign(_) => EmojiStatus::InvalidTextAlign,
+            Error::InvalidFormat(_) => EmojiStatus::InvalidFormat,
+            Error::InvalidOption(_) => EmojiStatus::InvalidOption,
+            Error::FontLoadFailed(_) => EmojiStatus::FontLoadFailed,
+            Error::SurfaceCreationFailed => EmojiStatus::SurfaceCreationFailed,
+            Error::EncodeFailed => EmojiStatus::EncodeFailed,
+            Error::LimitsExceeded(_) => EmojiStatus::LimitsExceeded,
+            Error::ImageDecodeFailed(_) => EmojiStatus::ImageDecodeFailed,
+            Error::AlphaLoss(_) => EmojiStatus::AlphaLoss,
+        }
+    }
+}
+
+impl EmojiGenerator {
+    fn fail(&mut self, status: EmojiStatus, message: String) -> EmojiStatus {
+        // メッセージにNULが含まれているとCStringにできないため取り除く
+        self.last_error = Some(CString::new(message.replace('\0', "")).unwrap());
+        return status;
+    }
+
+    fn apply(&mut self, options: Options) -> EmojiStatus {
+        let result = self.generator.set_options(options);
+        if result.is_err() {
+            let error = result.unwrap_err();
+            return self.fail(EmojiStatus::from(&error), error.to_string());
+        }
+
+        return EmojiStatus::Ok;
+    }
+}
+
+// パニックがextern "C"の境界を越えて巻き戻らないよう、捕捉してエラーとして返す
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    return panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback);
+}
+
+unsafe fn to_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+
+    return CStr::from_ptr(value).to_str().ok();
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str) -> Option<HashMap<String, T>> {
+    let (key, value) = value.split_once('=')?;
+    let value = value.parse::<T>().ok()?;
+    return Some(HashMap::from([(key.to_string(), value)]));
+}
+
+// PATH@X,Y[,WxH] から重ねる画像を読み込む
+fn read_overlay(value: &str) -> Result<OverlayImage, String> {
+    let invalid = || format!("overlay_image must be PATH@X,Y[,WxH]: {}", value);
+    let (path, geometry) = value.rsplit_once('@').ok_or_else(invalid)?;
+    let parts: Vec<&str> = geometry.split(',').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let mut overlay = OverlayImage {
+        image: read_image(path)?,
+        x: parts[0].parse().map_err(|_| invalid())?,
+        y: parts[1].parse().map_err(|_| invalid())?,
+        width: None,
+        height: None,
+    };
+    if parts.len() == 3 {
+        let (width, height) = parts[2].split_once('x').ok_or_else(invalid)?;
+        overlay.width = Some(width.parse().map_err(|_| invalid())?);
+        overlay.height = Some(height.parse().map_err(|_| invalid())?);
+    }
+
+    return Ok(overlay);
+}
+
+fn read_image(path: &str) -> Result<Vec<u8>, String> {
+    let data = fs::read(path);
+    if data.is_err() {
+        return Err(format!("Failed to read {}: {}", path, data.unwrap_err()));
+    }
+
+    return Ok(data.unwrap());
+}
+
+// 画像のオプションはファイルのパスで指定する
+fn image_option(key: &str, value: &str) -> Result<Options, String> {
+    let mut options = Options::default();
+    match key {
+        "fill_image" => options.fill_image = Some(read_image(value)?),
+        "background_image" => options.background_image = Some(read_image(value)?),
+        _ => options.overlay_images = Some(vec![read_overlay(value)?]),
+    }
+
+    return Ok(options);
+}
+
+// キーと文字列の値から1項目だけのオプションを作る (値が不正ならNone)
+fn option_from_pair(options: &mut Options, key: &str, value: &str) -> Option<()> {
+    match key {
+        "width" => options.width = Some(value.parse().ok()?),
+        "height" => options.height = Some(value.parse().ok()?),
+        "flexible_width" => options.flexible_width = Some(parse_bool(value)?),
+        "color" => options.color = Some(value.to_string()),
+        "color_palette" => options.color_palette = Some(value.split(';').map(|color| color.trim().to_string()).collect()),
+        "background_color" => options.background_color = Some(value.to_string()),
+        "text_align" => options.text_align = Some(value.to_string()),
+        "text_size_fixed" => options.text_size_fixed = Some(parse_bool(value)?),
+        "disable_stretch" => options.disable_stretch = Some(parse_bool(value)?),
+        "disable_outline" => options.disable_outline = Some(parse_bool(value)?),
+        "outline_width" => options.outline_width = Some(value.parse().ok()?),
+        "outline_color" => options.outline_color = Some(value.to_string()),
+        "shape" => options.shape = Some(value.to_string()),
+        "shape_color" => options.shape_color = Some(value.to_string()),
+        "shape_border_width" => options.shape_border_width = Some(value.parse().ok()?),
+        "shape_border_color" => options.shape_border_color = Some(value.to_string()),
+        "shape_radius" => options.shape_radius = Some(value.parse().ok()?),
+        "shape_padding" => options.shape_padding = Some(value.parse().ok()?),
+        "typeface_name" => options.typeface_name = Some(value.to_string()),
+        "typeface_weight" => options.typeface_weight = Some(value.parse().ok()?),
+        "typeface_width" => options.typeface_width = Some(value.to_string()),
+        "typeface_slant" => options.typeface_slant = Some(value.to_string()),
+        "typeface_variation" => options.typeface_variations = Some(parse_pair(value)?),
+        "fit_width_axis" => options.fit_width_axis = Some(parse_bool(value)?),
+        "opentype_feature" => options.opentype_features = Some(parse_pair(value)?),
+        "letter_spacing" => options.letter_spacing = Some(value.to_string()),
+        "fit_precision" => options.fit_precision = Some(value.parse().ok()?),
+        "font_hinting" => options.font_hinting = Some(value.to_string()),
+        "font_edging" => options.font_edging = Some(value.to_string()),
+        "font_subpixel" => options.font_subpixel = Some(parse_bool(value)?),
+        "supersample" => options.supersample = Some(value.parse().ok()?),
+        "pixel_art" => options.pixel_art = Some(parse_bool(value)?),
+        "palette_size" => options.palette_size = Some(value.parse().ok()?),
+        "max_bytes" => options.max_bytes = Some(value.parse().ok()?),
+        "format" => options.format = Some(value.to_string()),
+        "matte_color" => options.matte_color = Some(value.to_string()),
+        "alpha_loss" => options.alpha_loss = Some(value.to_string()),
+        "trim" => options.trim = Some(value.to_string()),
+        "trim_padding" => options.trim_padding = Some(value.parse().ok()?),
+        "quality" => options.quality = Some(value.parse().ok()?),
+        _ => return None,
+    }
+
+    return Some(());
+}
+
+/// Creates a generator with the default options. Free it with `emoji_generator_free`.
+#[no_mangle]
+pub extern "C" fn emoji_generator_new() -> *mut EmojiGenerator {
+    return guard(ptr::null_mut(), || {
+        let generator = EmojiGenerator {
+            generator: Generator::new(),
+            typeface_file: None,
+            typeface_index: 0,
+            last_error: None,
+            warnings: None,
+            has_text: false,
+        };
+
+        return Box::into_raw(Box::new(generator));
+    });
+}
+
+/// Frees a generator. Does nothing if `generator` is NULL.
+///
+/// # Safety
+/// `generator` must be NULL or a pointer returned by `emoji_generator_new` which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_free(generator: *mut EmojiGenerator) {
+    guard((), || {
+        if !generator.is_null() {
+            drop(Box::from_raw(generator));
+        }
+    });
+}
+
+/// Sets the text to render. Lines are separated by `\n`.
+///
+/// # Safety
+/// `generator` must be a valid generator and `text` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_set_text(generator: *mut EmojiGenerator, text: *const c_char) -> EmojiStatus {
+    return guard(EmojiStatus::InternalError, || {
+        if generator.is_null() {
+            return EmojiStatus::InvalidArgument;
+        }
+
+        let handle = &mut *generator;
+        let text = to_str(text);
+        if text.is_none() {
+            return handle.fail(EmojiStatus::InvalidArgument, "text must be a UTF-8 string.".to_string());
+        }
+
+        handle.generator.set_texts(text.unwrap().to_string());
+        handle.has_text = true;
+        return EmojiStatus::Ok;
+    });
+}
+
+/// Sets an option by its snake_case name (eg. `"color"`, `"#FF0000"`).
+///
+/// Numbers and booleans (`true` / `false`) are passed as strings. `typeface_variation` and
+/// `opentype_feature` take `KEY=VALUE` and can be set repeatedly. `color_palette` takes colors separated by `;`.
+/// `fill_image` and `background_image` take image file paths. `overlay_image` takes `PATH@X,Y` or `PATH@X,Y,WxH`
+/// and can be set repeatedly to draw several images.
+///
+/// # Safety
+/// `generator` must be a valid generator and `key` / `value` NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_set_option(generator: *mut EmojiGenerator, key: *const c_char, value: *const c_char) -> EmojiStatus {
+    return guard(EmojiStatus::InternalError, || {
+        if generator.is_null() {
+            return EmojiStatus::InvalidArgument;
+        }
+
+        let handle = &mut *generator;
+        let (key, value) = match (to_str(key), to_str(value)) {
+            (Some(key), Some(value)) => (key, value),
+            _ => return handle.fail(EmojiStatus::InvalidArgument, "key and value must be UTF-8 strings.".to_string()),
+        };
+
+        // フォントファイルとインデックスはどちらを先に設定しても同じ結果になるようにする
+        if key == "typeface_file" || key == "typeface_index" {
+            if key == "typeface_file" {
+                handle.typeface_file = Some(value.to_string());
+            } else {
+                let index = value.parse::<u32>();
+                if index.is_err() {
+                    return handle.fail(EmojiStatus::InvalidOption, format!("Invalid value for {}: {}", key, value));
+                }
+                handle.typeface_index = index.unwrap();
+            }
+
+            if handle.typeface_file.is_none() {
+                return EmojiStatus::Ok;
+            }
+
+            let options = Options {
+                typeface_file: handle.typeface_file.clone(),
+                typeface_index: Some(handle.typeface_index),
+                ..Default::default()
+            };
+            return handle.apply(options);
+        }
+
+        if key == "fill_image" || key == "background_image" || key == "overlay_image" {
+            let options = image_option(key, value);
+            if options.is_err() {
+                return handle.fail(EmojiStatus::InvalidOption, options.unwrap_err());
+            }
+            return handle.apply(options.unwrap());
+        }
+
+        let mut options = Options::default();
+        if option_from_pair(&mut options, key, value).is_none() {
+            return handle.fail(EmojiStatus::InvalidOption, format!("Invalid option {}: {}", key, value));
+        }
+
+        return handle.apply(options);
+    });
+}
+
+/// Generates the image. On success `*data` and `*length` receive the encoded image,
+/// which must be freed with `emoji_buffer_free`.
+/// Returns `EMOJI_STATUS_INVALID_ARGUMENT` if no text has been set with `emoji_generator_set_text`.
+///
+/// # Safety
+/// `generator` must be a valid generator and `data` / `length` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_generate(generator: *mut EmojiGenerator, data: *mut *mut u8, length: *mut usize) -> EmojiStatus {
+    return guard(EmojiStatus::InternalError, || {
+        if generator.is_null() {
+            return EmojiStatus::InvalidArgument;
+        }
+
+        let handle = &mut *generator;
+        if data.is_null() || length.is_null() {
+            return handle.fail(EmojiStatus::InvalidArgument, "data and length must not be NULL.".to_string());
+        }
+        if !handle.has_text {
+            return handle.fail(EmojiStatus::InvalidArgument, "text must be set with emoji_generator_set_text.".to_string());
+        }
+
+        let result = handle.generator.generate();
+        if result.is_err() {
+            let error = result.unwrap_err();
+            return handle.fail(EmojiStatus::from(&error), error.to_string());
+        }
+
+        let warnings = handle.generator.get_warnings();
+        handle.warnings = if warnings.is_empty() { None } else { Some(CString::new(warnings.join("\n").replace('\0', "")).unwrap()) };
+
+        let buffer = result.unwrap().as_bytes().to_vec().into_boxed_slice();
+        *length = buffer.len();
+        *data = Box::into_raw(buffer) as *mut u8;
+        return EmojiStatus::Ok;
+    });
+}
+
+/// Stores the rectangle cropped by the last successful `emoji_generator_generate` in `*rect`.
+/// Returns false (leaving `*rect` untouched) if `trim` is not set or no image has been generated.
+///
+/// # Safety
+/// `generator` must be a valid generator and `rect` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_crop_rect(generator: *const EmojiGenerator, rect: *mut EmojiCropRect) -> bool {
+    return guard(false, || {
+        if generator.is_null() || rect.is_null() {
+            return false;
+        }
+
+        match (*generator).generator.get_crop_rect() {
+            Some(crop_rect) => {
+                *rect = EmojiCropRect {
+                    x: crop_rect.x,
+                    y: crop_rect.y,
+                    width: crop_rect.width,
+                    height: crop_rect.height,
+                };
+                true
+            },
+            None => false,
+        }
+    });
+}
+
+/// Returns the warnings of the last successful `emoji_generator_generate` separated by `\n`
+/// (eg. with `alpha_loss` set to `warn`), or NULL if there are none.
+/// The string is valid until the next call with the same generator.
+///
+/// # Safety
+/// `generator` must be a valid generator.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_warnings(generator: *const EmojiGenerator) -> *const c_char {
+    return guard(ptr::null(), || {
+        if generator.is_null() {
+            return ptr::null();
+        }
+
+        match &(*generator).warnings {
+            Some(warnings) => warnings.as_ptr(),
+            None => ptr::null(),
+        }
+    });
+}
+
+/// Returns the message of the last error on the generator, or NULL if no error has occurred.
+/// The string is valid until the next call with the same generator.
+///
+/// # Safety
+/// `generator` must be a valid generator.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_generator_last_error(generator: *const EmojiGenerator) -> *const c_char {
+    return guard(ptr::null(), || {
+        if generator.is_null() {
+            return ptr::null();
+        }
+
+        match &(*generator).last_error {
+            Some(message) => message.as_ptr(),
+            None => ptr::null(),
+        }
+    });
+}
+
+/// Frees an image returned by `emoji_generator_generate`. Does nothing if `data` is NULL.
+///
+/// # Safety
+/// `data` and `length` must be the values returned by `emoji_generator_generate`, and freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn emoji_buffer_free(data: *mut u8, length: usize) {
+    guard((), || {
+        if !data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, length)));
+        }
+    });
+}
@@ -1,5 +1,19 @@
+mod color;
+mod error;
+mod font;
 mod generator;
+mod image;
 mod line;
+mod options;
+mod shape;
+mod shaping;
 mod utils;
 
-pub use generator::Generator as generate;
\ No newline at end of file
+pub use color::ContrastReport;
+pub use error::Error;
+pub use font::{list_faces, list_fonts, register_typeface_data, register_typeface_file, FaceInfo, FontInfo, StyleInfo};
+pub use generator::{check_contrast, generate, generate_with_info, Generator, Output};
+pub use line::{LetterSpacing, Line, MeasureSpec};
+pub use image::{CropRect, ImageFit};
+pub use options::{Options, OverlayImage};
+pub use shape::{Shape, ShapeKind};
\ No newline at end of file
@@ -0,0 +1,452 @@
+use skia_safe::Color as SkColor;
+
+// CSSの名前付き色 (CSS Color Module Level 4)
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
+
+// CSSの色の構文 (名前付き色・16進数・rgb()・hsl()・hwb()) を解釈する
+pub fn parse_css_color(color: &str) -> Option<SkColor> {
+    let color = color.trim().to_ascii_lowercase();
+
+    if color == "transparent" {
+        return Some(SkColor::TRANSPARENT);
+    }
+
+    if let Some((_, rgb)) = NAMED_COLORS.iter().find(|(name, _)| *name == color) {
+        return Some(SkColor::new(0xFF000000 | rgb));
+    }
+
+    if let Some(open) = color.find('(') {
+        if !color.ends_with(')') {
+            return None;
+        }
+
+        let name = color[..open].trim();
+        let args = parse_arguments(&color[open + 1..color.len() - 1]);
+        if args.is_none() {
+            return None;
+        }
+
+        let (values, alpha) = args.unwrap();
+        let alpha = match alpha {
+            Some(alpha) => parse_alpha(alpha),
+            None => Some(1.0),
+        };
+        if alpha.is_none() {
+            return None;
+        }
+
+        let rgb = match name {
+            "rgb" | "rgba" => parse_rgb(&values),
+            "hsl" | "hsla" => parse_hsl(&values),
+            "hwb" => parse_hwb(&values),
+            _ => None,
+        };
+        if rgb.is_none() {
+            return None;
+        }
+
+        let (r, g, b) = rgb.unwrap();
+        return Some(SkColor::from_argb(to_byte(alpha.unwrap()), to_byte(r), to_byte(g), to_byte(b)));
+    }
+
+    return parse_hex(color.strip_prefix('#').unwrap_or(&color));
+}
+
+// #RGB・#RGBA・#RRGGBB・#RRGGBBAA (#は省略可)
+fn parse_hex(hex: &str) -> Option<SkColor> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // 3桁・4桁の場合は各桁を2桁に展開する
+    let hex = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 | 8 => hex.to_string(),
+        _ => return None,
+    };
+
+    let value = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    let a = if hex.len() == 8 { value(6) } else { 0xFF };
+    return Some(SkColor::from_argb(a, value(0), value(2), value(4)));
+}
+
+// 関数の引数を値とアルファに分ける (カンマ区切りとスペース区切り+"/"の両方に対応)
+fn parse_arguments(args: &str) -> Option<(Vec<&str>, Option<&str>)> {
+    if args.contains(',') {
+        let mut values: Vec<&str> = args.split(',').map(|value| value.trim()).collect();
+        if values.len() == 4 {
+            let alpha = values.pop();
+            return Some((values, alpha));
+        }
+        if values.len() == 3 {
+            return Some((values, None));
+        }
+        return None;
+    }
+
+    let (values, alpha) = match args.split_once('/') {
+        Some((values, alpha)) => (values, Some(alpha.trim())),
+        None => (args, None),
+    };
+
+    let values: Vec<&str> = values.split_whitespace().collect();
+    if values.len() != 3 {
+        return None;
+    }
+
+    return Some((values, alpha));
+}
+
+// 数値またはパーセンテージを解釈する (パーセンテージは100%を`percent_scale`とする)
+fn parse_number(value: &str, percent_scale: f32) -> Option<f32> {
+    // CSS Color 4ではnoneは0として扱う
+    if value == "none" {
+        return Some(0.0);
+    }
+
+    let number = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0 * percent_scale,
+        None => value.parse::<f32>().ok()?,
+    };
+
+    if !number.is_finite() {
+        return None;
+    }
+
+    return Some(number);
+}
+
+fn parse_alpha(value: &str) -> Option<f32> {
+    return parse_number(value, 1.0).map(|alpha| alpha.clamp(0.0, 1.0));
+}
+
+// 色相を度に変換する
+fn parse_hue(value: &str) -> Option<f32> {
+    let units: [(&str, f32); 4] = [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / std::f32::consts::PI), ("turn", 360.0)];
+    for (unit, scale) in units {
+        if let Some(number) = value.strip_suffix(unit) {
+            return Some(number.parse::<f32>().ok()? * scale);
+        }
+    }
+
+    return parse_number(value, 360.0);
+}
+
+fn parse_rgb(values: &[&str]) -> Option<(f32, f32, f32)> {
+    let r = parse_number(values[0], 255.0)?;
+    let g = parse_number(values[1], 255.0)?;
+    let b = parse_number(values[2], 255.0)?;
+    return Some((r / 255.0, g / 255.0, b / 255.0));
+}
+
+fn parse_hsl(values: &[&str]) -> Option<(f32, f32, f32)> {
+    let h = parse_hue(values[0])?;
+    let s = (parse_number(values[1], 100.0)? / 100.0).clamp(0.0, 1.0);
+    let l = (parse_number(values[2], 100.0)? / 100.0).clamp(0.0, 1.0);
+    return Some(hsl_to_rgb(h, s, l));
+}
+
+fn parse_hwb(values: &[&str]) -> Option<(f32, f32, f32)> {
+    let h = parse_hue(values[0])?;
+    let w = (parse_number(values[1], 100.0)? / 100.0).clamp(0.0, 1.0);
+    let b = (parse_number(values[2], 100.0)? / 100.0).clamp(0.0, 1.0);
+
+    // 白と黒の合計が1を超える場合は比率を保ったまま正規化する
+    if w + b >= 1.0 {
+        let gray = w / (w + b);
+        return Some((gray, gray, gray));
+    }
+
+    let (red, green, blue) = hsl_to_rgb(h, 1.0, 0.5);
+    let scale = 1.0 - w - b;
+    return Some((red * scale + w, green * scale + w, blue * scale + w));
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0);
+    let channel = |n: f32| {
+        let k = (n + h / 30.0) % 12.0;
+        let a = s * l.min(1.0 - l);
+        l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+
+    return (channel(0.0), channel(8.0), channel(4.0));
+}
+
+fn to_byte(value: f32) -> u8 {
+    return (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+}
+
+// 十分なコントラストとみなす比率 (WCAG 2.xのAA・大きな文字の基準)
+pub const MIN_CONTRAST_RATIO: f32 = 3.0;
+
+// オプションの組み合わせのコントラスト比 (背景が透明な場合、背景との比率はNone)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastReport {
+    pub outline_color: String,
+    pub text_background: Option<f32>,
+    pub outline_text: Option<f32>,
+    pub outline_background: Option<f32>,
+    pub sufficient: bool,
+}
+
+// WCAGの相対輝度
+pub fn relative_luminance(color: SkColor) -> f32 {
+    let channel = |value: u8| {
+        let value = value as f32 / 255.0;
+        if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+    };
+
+    return 0.2126 * channel(color.r()) + 0.7152 * channel(color.g()) + 0.0722 * channel(color.b());
+}
+
+// WCAGのコントラスト比 (1.0〜21.0)
+pub fn contrast_ratio(a: SkColor, b: SkColor) -> f32 {
+    let a = relative_luminance(a);
+    let b = relative_luminance(b);
+    return (a.max(b) + 0.05) / (a.min(b) + 0.05);
+}
+
+// topをbottomの上に重ねた色 (source-over)
+pub fn composite(top: SkColor, bottom: SkColor) -> SkColor {
+    let top_alpha = top.a() as f32 / 255.0;
+    let bottom_alpha = bottom.a() as f32 / 255.0 * (1.0 - top_alpha);
+    let alpha = top_alpha + bottom_alpha;
+    if alpha == 0.0 {
+        return SkColor::TRANSPARENT;
+    }
+
+    let blend = |top: u8, bottom: u8| ((top as f32 * top_alpha + bottom as f32 * bottom_alpha) / alpha).round() as u8;
+    return SkColor::from_argb((alpha * 255.0).round() as u8, blend(top.r(), bottom.r()), blend(top.g(), bottom.g()), blend(top.b(), bottom.b()));
+}
+
+// 半透明の背景は白の上に合成した色として扱う (完全に透明ならNone)
+pub fn opaque_background(background: SkColor) -> Option<SkColor> {
+    if background.a() == 0 {
+        return None;
+    }
+
+    let alpha = background.a() as f32 / 255.0;
+    let blend = |value: u8| (value as f32 * alpha + 255.0 * (1.0 - alpha)).round() as u8;
+    return Some(SkColor::from_rgb(blend(background.r()), blend(background.g()), blend(background.b())));
+}
+
+// 文字色と背景色の両方に対するコントラストが最も高くなる灰色を縁取りの色として選ぶ
+pub fn auto_outline_color(fill: SkColor, background: SkColor) -> SkColor {
+    let background = opaque_background(background);
+    let score = |outline: SkColor| {
+        let text = contrast_ratio(outline, fill);
+        match background {
+            Some(background) => text.min(contrast_ratio(outline, background)),
+            None => text,
+        }
+    };
+
+    let mut best = SkColor::BLACK;
+    let mut best_score = score(best);
+    for value in 1..=255u8 {
+        let outline = SkColor::from_rgb(value, value, value);
+        let outline_score = score(outline);
+        if outline_score > best_score {
+            best = outline;
+            best_score = outline_score;
+        }
+    }
+
+    return best;
+}
+
+pub fn to_hex(color: SkColor) -> String {
+    return format!("#{:02X}{:02X}{:02X}{:02X}", color.r(), color.g(), color.b(), color.a());
+}
+
+// テキストのハッシュ (FNV-1a)。バージョンや環境によって色が変わらないよう標準のHasherは使わない
+pub fn text_hash(text: &str) -> u32 {
+    let mut hash: u32 = 0x811C9DC5;
+    for byte in text.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+
+    return hash;
+}
+
+// テキストから文字色を決める (パレットがあればその中から選ぶ)
+pub fn hash_color(text: &str, palette: &[SkColor]) -> SkColor {
+    let hash = text_hash(text);
+    if !palette.is_empty() {
+        return palette[hash as usize % palette.len()];
+    }
+
+    let (r, g, b) = hsl_to_rgb((hash % 360) as f32, 0.7, 0.45);
+    return SkColor::from_rgb(to_byte(r), to_byte(g), to_byte(b));
+}
+
+// テキストから縁取りの色を決める (パレットでは文字色の次の色、無ければ文字色と同じ色相の淡い色)
+// 色が1つだけのパレットや、選んだ色が文字色と同じで縁取りが見えなくなる場合はautoと同じ色にする
+pub fn hash_outline_color(text: &str, palette: &[SkColor], fill: SkColor, background: SkColor) -> SkColor {
+    let hash = text_hash(text);
+    let outline = match palette.len() {
+        0 => {
+            let (r, g, b) = hsl_to_rgb((hash % 360) as f32, 0.7, 0.92);
+            SkColor::from_rgb(to_byte(r), to_byte(g), to_byte(b))
+        },
+        1 => return auto_outline_color(fill, background),
+        len => palette[(hash as usize + 1) % len],
+    };
+
+    if outline == fill {
+        return auto_outline_color(fill, background);
+    }
+
+    return outline;
+}
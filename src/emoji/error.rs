@@ -0,0 +1,52 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    InvalidColor(String),
+    InvalidTextAlign(String),
+    InvalidFormat(String),
+    InvalidOption(String),
+    FontLoadFailed(String),
+    SurfaceCreationFailed,
+    EncodeFailed,
+    LimitsExceeded(String),
+    ImageDecodeFailed(String),
+    AlphaLoss(String),
+}
+
+impl Error {
+    // JavaScript側に公開するエラーコード (変更しないこと)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidColor(_) => "InvalidColor",
+            Error::InvalidTextAlign(_) => "InvalidTextAlign",
+            Error::InvalidFormat(_) => "InvalidFormat",
+            Error::InvalidOption(_) => "InvalidOption",
+            Error::FontLoadFailed(_) => "FontLoadFailed",
+            Error::SurfaceCreationFailed => "SurfaceCreationFailed",
+            Error::EncodeFailed => "EncodeFailed",
+            Error::LimitsExceeded(_) => "LimitsExceeded",
+            Error::ImageDecodeFailed(_) => "ImageDecodeFailed",
+            Error::AlphaLoss(_) => "AlphaLoss",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidColor(message)
+            | Error::InvalidTextAlign(message)
+            | Error::InvalidFormat(message)
+            | Error::InvalidOption(message)
+            | Error::FontLoadFailed(message)
+            | Error::LimitsExceeded(message)
+            | Error::ImageDecodeFailed(message)
+            | Error::AlphaLoss(message) => write!(f, "{}", message),
+            Error::SurfaceCreationFailed => write!(f, "Failed to create surface."),
+            Error::EncodeFailed => write!(f, "Failed to encode image."),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
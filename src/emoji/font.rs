@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+use super::error::Error;
+use skia_safe::Data as SkData;
+use skia_safe::typeface::Typeface as SkTypeface;
+use skia_safe::font_style::Slant as SkSlant;
+use skia_safe::FontMgr as SkFontMgr;
+use skia_safe::FontStyle as SkFontStyle;
+
+// 対応文字種の判定に使う代表的な文字
+const SCRIPT_SAMPLES: [(&str, &str); 7] = [
+    ("latin", "Aa"),
+    ("greek", "Ωω"),
+    ("cyrillic", "Жж"),
+    ("hiragana", "あん"),
+    ("katakana", "アン"),
+    ("kanji", "漢字"),
+    ("hangul", "한글"),
+];
+
+pub struct FaceInfo {
+    pub index: usize,
+    pub family: String,
+    pub weight: i32,
+    pub width: i32,
+    pub slant: String,
+}
+
+pub struct StyleInfo {
+    pub weight: i32,
+    pub width: i32,
+    pub slant: String,
+}
+
+pub struct FontInfo {
+    pub family: String,
+    pub registered: bool,
+    pub styles: Vec<StyleInfo>,
+    pub scripts: Vec<String>,
+}
+
+// プロセス全体で共有するフォントキャッシュ (パス+フェイス番号 -> 更新日時, フォント)
+type TypefaceCache = HashMap<(String, usize), (Option<SystemTime>, SkTypeface)>;
+
+fn typeface_cache() -> &'static Mutex<TypefaceCache> {
+    static CACHE: OnceLock<Mutex<TypefaceCache>> = OnceLock::new();
+    return CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+}
+
+// registerFontで登録されたフォント (別名 -> フォント)
+fn registered_typefaces() -> &'static Mutex<HashMap<String, SkTypeface>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, SkTypeface>>> = OnceLock::new();
+    return REGISTRY.get_or_init(|| Mutex::new(HashMap::new()));
+}
+
+pub fn load_typeface_file(path: String, index: usize) -> Result<SkTypeface, Error> {
+    let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+    let key = (path.to_string(), index);
+
+    // 更新日時が変わっていなければキャッシュを返す
+    if let Some((cached_modified, tf)) = typeface_cache().lock().unwrap().get(&key) {
+        if *cached_modified == modified {
+            return Ok(tf.clone());
+        }
+    }
+
+    let result = read_typeface_file(path.to_string());
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    let tf = SkTypeface::from_data(result.unwrap(), index);
+    if tf.is_none() {
+        return Err(Error::FontLoadFailed(format!("Invalid typeface file: {}", path)));
+    }
+
+    let tf = tf.unwrap();
+    typeface_cache().lock().unwrap().insert(key, (modified, tf.clone()));
+    return Ok(tf);
+}
+
+pub fn register_typeface_file(alias: String, path: String, index: usize) -> Result<(), Error> {
+    let result = load_typeface_file(path, index);
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    registered_typefaces().lock().unwrap().insert(alias, result.unwrap());
+    return Ok(());
+}
+
+pub fn register_typeface_data(alias: String, data: &[u8], index: usize) -> Result<(), Error> {
+    let tf = SkTypeface::from_data(SkData::new_copy(data), index);
+    if tf.is_none() {
+        return Err(Error::FontLoadFailed(format!("Invalid typeface data for: {}", alias)));
+    }
+
+    registered_typefaces().lock().unwrap().insert(alias, tf.unwrap());
+    return Ok(());
+}
+
+pub fn get_registered_typeface(alias: &str) -> Option<SkTypeface> {
+    return registered_typefaces().lock().unwrap().get(alias).cloned();
+}
+
+pub fn read_typeface_file(path: String) -> Result<SkData, Error> {
+    let file = File::open(&path);
+    if file.is_err() {
+        return Err(Error::FontLoadFailed(format!("Failed to open typeface file: {}", path)));
+    }
+
+    let mut buf = Vec::new();
+    let result = file.unwrap().read_to_end(&mut buf);
+    if result.is_err() {
+        return Err(Error::FontLoadFailed(format!("Failed to read typeface file: {}", path)));
+    }
+
+    return Ok(SkData::new_copy(&buf));
+}
+
+// TrueType/OpenTypeコレクション (.ttc/.otc) に含まれるフェイス数を取得する
+pub fn count_faces(data: &SkData) -> usize {
+    let bytes = data.as_bytes();
+
+    // コレクションでなければ単一フェイス
+    if bytes.len() < 12 || &bytes[0..4] != b"ttcf" {
+        return 1;
+    }
+
+    // ヘッダーの値は信用せず、実際に格納できるオフセットテーブルの数までに制限する
+    let count = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize;
+    return count.min((bytes.len() - 12) / 4);
+}
+
+pub fn list_faces(path: String) -> Result<Vec<FaceInfo>, Error> {
+    let result = read_typeface_file(path.to_string());
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    let data = result.unwrap();
+    let mut faces = Vec::new();
+    for index in 0..count_faces(&data) {
+        let tf = SkTypeface::from_data(data.clone(), index);
+        if tf.is_none() {
+            continue;
+        }
+
+        let tf = tf.unwrap();
+        let style = tf.font_style();
+        faces.push(FaceInfo {
+            index,
+            family: tf.family_name(),
+            weight: *style.weight(),
+            width: *style.width(),
+            slant: slant_to_string(style.slant()),
+        });
+    }
+
+    if faces.is_empty() {
+        return Err(Error::FontLoadFailed(format!("Invalid typeface file: {}", path)));
+    }
+
+    return Ok(faces);
+}
+
+pub fn slant_to_string(slant: SkSlant) -> String {
+    match slant {
+        SkSlant::Upright => "upright".to_string(),
+        SkSlant::Italic => "italic".to_string(),
+        SkSlant::Oblique => "oblique".to_string(),
+    }
+}
+
+pub fn style_info(style: SkFontStyle) -> StyleInfo {
+    return StyleInfo {
+        weight: *style.weight(),
+        width: *style.width(),
+        slant: slant_to_string(style.slant()),
+    };
+}
+
+// フォントが文字列中の全ての文字 (空白を除く) のグリフを持っているか
+pub fn has_glyphs(tf: &SkTypeface, text: &str) -> bool {
+    return text.chars().filter(|c| !c.is_whitespace()).all(|c| tf.unichar_to_glyph(c as i32) != 0);
+}
+
+pub fn covered_scripts(tf: &SkTypeface) -> Vec<String> {
+    return SCRIPT_SAMPLES.iter().filter(|(_, sample)| has_glyphs(tf, sample)).map(|(script, _)| script.to_string()).collect();
+}
+
+// 登録済みフォントとSkiaのフォントマネージャから利用可能なフォントを列挙する
+// textを指定した場合は、その文字列を描画できるフォントのみ返す
+pub fn list_fonts(text: Option<String>) -> Vec<FontInfo> {
+    let mut fonts = Vec::new();
+
+    let mut registered: Vec<(String, SkTypeface)> = registered_typefaces().lock().unwrap().iter().map(|(alias, tf)| (alias.to_string(), tf.clone())).collect();
+    registered.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (alias, tf) in registered {
+        if text.as_ref().is_some_and(|text| !has_glyphs(&tf, text)) {
+            continue;
+        }
+
+        fonts.push(FontInfo {
+            family: alias,
+            registered: true,
+            styles: vec![style_info(tf.font_style())],
+            scripts: covered_scripts(&tf),
+        });
+    }
+
+    let mgr = SkFontMgr::new();
+    for i in 0..mgr.count_families() {
+        let mut style_set = mgr.new_style_set(i);
+        if style_set.count() == 0 {
+            continue;
+        }
+
+        let mut styles = Vec::new();
+        for j in 0..style_set.count() {
+            styles.push(style_info(style_set.style(j).0));
+        }
+
+        // 文字種の判定はファミリーの標準スタイルで行う
+        let tf = style_set.match_style(0, SkFontStyle::normal());
+        if tf.is_none() {
+            continue;
+        }
+
+        let tf = tf.unwrap();
+        if text.as_ref().is_some_and(|text| !has_glyphs(&tf, text)) {
+            continue;
+        }
+
+        fonts.push(FontInfo {
+            family: mgr.family_name(i),
+            registered: false,
+            styles,
+            scripts: covered_scripts(&tf),
+        });
+    }
+
+    return fonts;
+}
@@ -1,17 +1,42 @@
 #![allow(dead_code)]
 
+use super::color::{self, ContrastReport};
+use super::error::Error;
 use super::utils;
-use super::line::Line;
-use std::fs::File;
-use std::io::Read;
+use super::font;
+use super::line::{Line, LetterSpacing};
+use super::image::{self, AlphaLoss, CropRect, ImageFit, Overlay, Trim};
+use super::options::Options;
+use super::shape::{Shape, ShapeKind};
 use skia_safe::ISize;
 use skia_safe::utils::text_utils::Align as SkTextAlign;
 use skia_safe::Color as SkColor;
+use skia_safe::FontHinting as SkFontHinting;
+use skia_safe::font::Edging as SkFontEdging;
 use skia_safe::EncodedImageFormat as SkEncodedImageFormat;
 use skia_safe::Data as SkData;
+use skia_safe::Image as SkImage;
+use skia_safe::TileMode as SkTileMode;
 use skia_safe::surfaces as SkSurfaces;
 use skia_safe::typeface::Typeface as SkTypeface;
 use skia_safe::FontStyle as SkFontStyle;
+use skia_safe::font_style::Weight as SkFontWeight;
+use skia_safe::font_style::Width as SkFontWidth;
+use skia_safe::font_style::Slant as SkFontSlant;
+use skia_safe::FontArguments as SkFontArguments;
+use skia_safe::font_arguments::VariationPosition as SkVariationPosition;
+use skia_safe::font_arguments::variation_position::Coordinate as SkCoordinate;
+use skia_safe::FourByteTag as SkFourByteTag;
+
+// 生成できる画像の最大サイズ (幅・高さそれぞれ)
+const MAX_IMAGE_SIZE: f32 = 16384.0;
+// スーパーサンプリングの最大倍率
+const MAX_SUPERSAMPLE: u32 = 8;
+// 色数を制限する場合の最大色数 (インデックスカラーの上限)
+const MAX_PALETTE_SIZE: u32 = 256;
+// maxBytesに収めるために試す色数・JPEGの品質 (大きい順)
+const BUDGET_PALETTE_SIZES: [u32; 8] = [256, 128, 64, 32, 16, 8, 4, 2];
+const BUDGET_QUALITIES: [u32; 9] = [90, 80, 70, 60, 50, 40, 30, 20, 10];
 
 pub struct Generator {
     texts: Vec<String>,
@@ -19,6 +44,11 @@ pub struct Generator {
     height: f32,
     flexible_width: bool,
     color: SkColor,
+    color_hash: bool,
+    color_palette: Vec<SkColor>,
+    fill_image: Option<SkImage>,
+    fill_image_tile_mode: SkTileMode,
+    fill_image_scale: f32,
     background_color: SkColor,
     text_align: SkTextAlign,
     text_size_fixed: bool,
@@ -26,11 +56,48 @@ pub struct Generator {
     disable_outline: bool,
     outline_width: f32,
     outline_color: SkColor,
+    outline_color_auto: bool,
+    outline_color_hash: bool,
+    shape: Shape,
+    background_image: Option<SkImage>,
+    background_image_fit: ImageFit,
+    overlay_images: Vec<Overlay>,
+    trim: Trim,
+    trim_padding: u32,
+    crop_rect: Option<CropRect>,
     typeface: SkTypeface,
+    typeface_name: Option<String>,
+    typeface_weight: SkFontWeight,
+    typeface_width: SkFontWidth,
+    typeface_slant: SkFontSlant,
+    typeface_variations: Vec<SkCoordinate>,
+    fit_width_axis: bool,
+    opentype_features: Vec<(SkFourByteTag, u32)>,
+    letter_spacing: LetterSpacing,
+    fit_precision: f32,
+    font_hinting: SkFontHinting,
+    font_edging: SkFontEdging,
+    font_subpixel: bool,
+    supersample: u32,
+    pixel_art: bool,
+    palette_size: Option<u32>,
+    max_bytes: Option<u32>,
+    max_pixels: Option<u64>,
+    adjustments: Vec<String>,
+    warnings: Vec<String>,
+    output_format: SkEncodedImageFormat,
     format: SkEncodedImageFormat,
+    matte_color: SkColor,
+    alpha_loss: AlphaLoss,
     quality: u32,
 }
 
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Generator {
     pub fn new() -> Self {
         Self {
@@ -39,6 +106,11 @@ impl Generator {
             height: 128.0,
             flexible_width: false,
             color: SkColor::BLACK,
+            color_hash: false,
+            color_palette: Vec::new(),
+            fill_image: None,
+            fill_image_tile_mode: SkTileMode::Repeat,
+            fill_image_scale: 1.0,
             background_color: SkColor::TRANSPARENT,
             text_align: SkTextAlign::Center,
             text_size_fixed: false,
@@ -46,8 +118,39 @@ impl Generator {
             disable_outline: false,
             outline_width: 8.0,
             outline_color: SkColor::WHITE,
+            outline_color_auto: false,
+            outline_color_hash: false,
+            shape: Shape::new(),
+            background_image: None,
+            background_image_fit: ImageFit::Cover,
+            overlay_images: Vec::new(),
+            trim: Trim::None,
+            trim_padding: 0,
+            crop_rect: None,
             typeface: SkTypeface::default(),
+            typeface_name: None,
+            typeface_weight: SkFontWeight::NORMAL,
+            typeface_width: SkFontWidth::NORMAL,
+            typeface_slant: SkFontSlant::Upright,
+            typeface_variations: Vec::new(),
+            fit_width_axis: false,
+            opentype_features: Vec::new(),
+            letter_spacing: LetterSpacing::Absolute(0.0),
+            fit_precision: 0.5,
+            font_hinting: SkFontHinting::Normal,
+            font_edging: SkFontEdging::AntiAlias,
+            font_subpixel: false,
+            supersample: 1,
+            pixel_art: false,
+            palette_size: None,
+            max_bytes: None,
+            max_pixels: None,
+            adjustments: Vec::new(),
+            warnings: Vec::new(),
+            output_format: SkEncodedImageFormat::PNG,
             format: SkEncodedImageFormat::PNG,
+            matte_color: SkColor::WHITE,
+            alpha_loss: AlphaLoss::Ignore,
             quality: 100,
         }
     }
@@ -68,16 +171,67 @@ impl Generator {
         self.flexible_width = flexible_width;
     }
 
-    pub fn set_color(&mut self, color: String) -> Result<(), String> {
+    pub fn set_color(&mut self, color: String) -> Result<(), Error> {
+        // hashの場合は生成時にテキストから決める
+        if color == "hash" {
+            self.color_hash = true;
+            return Ok(());
+        }
+
         let result = utils::parse_color_code(color);
         if result.is_err() {
             return Err(result.unwrap_err());
         }
         self.color = result.unwrap();
+        self.color_hash = false;
+        return Ok(());
+    }
+
+    pub fn set_color_palette(&mut self, palette: Vec<String>) -> Result<(), Error> {
+        let mut colors = Vec::new();
+        for color in palette {
+            let result = utils::parse_color_code(color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+            colors.push(result.unwrap());
+        }
+
+        self.color_palette = colors;
+        return Ok(());
+    }
+
+    pub fn set_fill_image(&mut self, data: &[u8]) -> Result<(), Error> {
+        let result = image::decode_image(data);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.fill_image = Some(result.unwrap());
+        return Ok(());
+    }
+
+    pub fn set_fill_image_tile_mode(&mut self, tile_mode: SkTileMode) {
+        self.fill_image_tile_mode = tile_mode;
+    }
+
+    pub fn set_fill_image_tile_mode_by_string(&mut self, tile_mode: String) -> Result<(), Error> {
+        let result = utils::parse_tile_mode(tile_mode);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.fill_image_tile_mode = result.unwrap();
         return Ok(());
     }
 
-    pub fn set_background_color(&mut self, background_color: String) -> Result<(), String> {
+    pub fn set_fill_image_scale(&mut self, scale: f32) -> Result<(), Error> {
+        if scale.is_nan() || scale <= 0.0 {
+            return Err(Error::InvalidOption(format!("Fill image scale must be greater than 0: {}", scale)));
+        }
+        self.fill_image_scale = scale;
+        return Ok(());
+    }
+
+    pub fn set_background_color(&mut self, background_color: String) -> Result<(), Error> {
         let result = utils::parse_color_code(background_color);
         if result.is_err() {
             return Err(result.unwrap_err());
@@ -90,7 +244,7 @@ impl Generator {
         self.text_align = text_align;
     }
 
-    pub fn set_text_align_by_string(&mut self, text_align: String) -> Result<(), String> {
+    pub fn set_text_align_by_string(&mut self, text_align: String) -> Result<(), Error> {
         let result = utils::parse_text_align(text_align);
         if result.is_err() {
             return Err(result.unwrap_err());
@@ -115,53 +269,309 @@ impl Generator {
         self.outline_width = outline_width as f32;
     }
 
-    pub fn set_outline_color(&mut self, outline_color: String) -> Result<(), String> {
+    pub fn set_outline_color(&mut self, outline_color: String) -> Result<(), Error> {
+        // autoの場合は文字色と背景色から、hashの場合はテキストから生成時に決める
+        if outline_color == "auto" || outline_color == "hash" {
+            self.outline_color_auto = outline_color == "auto";
+            self.outline_color_hash = outline_color == "hash";
+            return Ok(());
+        }
+
         let result = utils::parse_color_code(outline_color);
         if result.is_err() {
             return Err(result.unwrap_err());
         }
         self.outline_color = result.unwrap();
+        self.outline_color_auto = false;
+        self.outline_color_hash = false;
+        return Ok(());
+    }
+
+    pub fn set_shape(&mut self, shape: ShapeKind) {
+        self.shape.set_kind(shape);
+    }
+
+    pub fn set_shape_by_string(&mut self, shape: String) -> Result<(), Error> {
+        let result = utils::parse_shape(shape);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.shape.set_kind(result.unwrap());
+        return Ok(());
+    }
+
+    pub fn set_shape_color(&mut self, shape_color: String) -> Result<(), Error> {
+        let result = utils::parse_color_code(shape_color);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.shape.set_color(result.unwrap());
+        return Ok(());
+    }
+
+    pub fn set_shape_border_width(&mut self, shape_border_width: u32) {
+        self.shape.set_border_width(shape_border_width as f32);
+    }
+
+    pub fn set_shape_border_color(&mut self, shape_border_color: String) -> Result<(), Error> {
+        let result = utils::parse_color_code(shape_border_color);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.shape.set_border_color(result.unwrap());
+        return Ok(());
+    }
+
+    pub fn set_shape_radius(&mut self, shape_radius: u32) {
+        self.shape.set_radius(shape_radius as f32);
+    }
+
+    pub fn set_shape_padding(&mut self, shape_padding: u32) {
+        self.shape.set_padding(shape_padding as f32);
+    }
+
+    pub fn set_background_image(&mut self, data: &[u8]) -> Result<(), Error> {
+        let result = image::decode_image(data);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.background_image = Some(result.unwrap());
+        return Ok(());
+    }
+
+    pub fn set_background_image_fit(&mut self, fit: ImageFit) {
+        self.background_image_fit = fit;
+    }
+
+    pub fn set_background_image_fit_by_string(&mut self, fit: String) -> Result<(), Error> {
+        let result = utils::parse_image_fit(fit);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.background_image_fit = result.unwrap();
         return Ok(());
     }
 
-    pub fn set_typeface_file(&mut self, path: String) {
-        let file = File::open(path);
-        if file.is_err() {
-            return;
+    pub fn add_overlay_image(&mut self, data: &[u8], x: f32, y: f32, width: Option<u32>, height: Option<u32>) -> Result<(), Error> {
+        let result = image::decode_image(data);
+        if result.is_err() {
+            return Err(result.unwrap_err());
         }
 
-        let mut buf = Vec::new();
-        let result = file.unwrap().read_to_end(&mut buf);
+        self.overlay_images.push(Overlay {
+            image: result.unwrap(),
+            x,
+            y,
+            width: width.map(|width| width as f32),
+            height: height.map(|height| height as f32),
+        });
+        return Ok(());
+    }
+
+    pub fn set_trim(&mut self, trim: Trim) {
+        self.trim = trim;
+    }
+
+    pub fn set_trim_by_string(&mut self, trim: String) -> Result<(), Error> {
+        let result = utils::parse_trim(trim);
         if result.is_err() {
-            return;
+            return Err(result.unwrap_err());
         }
-        
-        let tf = SkTypeface::from_data(SkData::new_copy(&buf), 0);
-        if tf.is_none() {
-            return;
+        self.trim = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_trim_padding(&mut self, trim_padding: u32) {
+        self.trim_padding = trim_padding;
+    }
+
+    // 最後に生成した画像で切り取った範囲 (trimを指定していない場合はNone)
+    pub fn get_crop_rect(&self) -> Option<CropRect> {
+        return self.crop_rect;
+    }
+
+    pub fn set_typeface_file(&mut self, path: String, index: u32) -> Result<(), Error> {
+        let result = font::load_typeface_file(path, index as usize);
+        if result.is_err() {
+            return Err(result.unwrap_err());
         }
 
-        self.typeface = tf.unwrap();
+        self.typeface = result.unwrap();
+        return Ok(());
     }
 
     pub fn set_typeface_name(&mut self, name: String) {
-        let tf = SkTypeface::from_name(name, SkFontStyle::normal());
-        if tf.is_none() {
-            return;
+        self.typeface_name = Some(name);
+    }
+
+    pub fn set_typeface_weight(&mut self, weight: u32) {
+        self.typeface_weight = SkFontWeight::from(weight as i32);
+    }
+
+    pub fn set_typeface_width(&mut self, width: SkFontWidth) {
+        self.typeface_width = width;
+    }
+
+    pub fn set_typeface_width_by_string(&mut self, width: String) -> Result<(), Error> {
+        let result = utils::parse_font_width(width);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.typeface_width = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_typeface_slant(&mut self, slant: SkFontSlant) {
+        self.typeface_slant = slant;
+    }
+
+    pub fn set_typeface_slant_by_string(&mut self, slant: String) -> Result<(), Error> {
+        let result = utils::parse_font_slant(slant);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.typeface_slant = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_typeface_variation(&mut self, axis: String, value: f32) -> Result<(), Error> {
+        let result = utils::parse_font_tag(axis);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+
+        let axis = result.unwrap();
+        self.typeface_variations.retain(|coordinate| coordinate.axis != axis);
+        self.typeface_variations.push(SkCoordinate { axis, value });
+        return Ok(());
+    }
+
+    pub fn set_fit_width_axis(&mut self, fit_width_axis: bool) {
+        self.fit_width_axis = fit_width_axis;
+    }
+
+    pub fn set_opentype_feature(&mut self, tag: String, value: u32) -> Result<(), Error> {
+        let result = utils::parse_font_tag(tag);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+
+        let tag = result.unwrap();
+        self.opentype_features.retain(|feature| feature.0 != tag);
+        self.opentype_features.push((tag, value));
+        return Ok(());
+    }
+
+    pub fn set_letter_spacing(&mut self, letter_spacing: LetterSpacing) {
+        self.letter_spacing = letter_spacing;
+    }
+
+    pub fn set_letter_spacing_by_string(&mut self, letter_spacing: String) -> Result<(), Error> {
+        let result = utils::parse_letter_spacing(letter_spacing);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.letter_spacing = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_fit_precision(&mut self, fit_precision: f32) -> Result<(), Error> {
+        if fit_precision.is_nan() || fit_precision <= 0.0 {
+            return Err(Error::InvalidOption(format!("Fit precision must be greater than 0: {}", fit_precision)));
+        }
+        self.fit_precision = fit_precision;
+        return Ok(());
+    }
+
+    pub fn set_font_hinting(&mut self, font_hinting: SkFontHinting) {
+        self.font_hinting = font_hinting;
+    }
+
+    pub fn set_font_hinting_by_string(&mut self, font_hinting: String) -> Result<(), Error> {
+        let result = utils::parse_font_hinting(font_hinting);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.font_hinting = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_font_edging(&mut self, font_edging: SkFontEdging) {
+        self.font_edging = font_edging;
+    }
+
+    pub fn set_font_edging_by_string(&mut self, font_edging: String) -> Result<(), Error> {
+        let result = utils::parse_font_edging(font_edging);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.font_edging = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_font_subpixel(&mut self, font_subpixel: bool) {
+        self.font_subpixel = font_subpixel;
+    }
+
+    pub fn set_supersample(&mut self, supersample: u32) -> Result<(), Error> {
+        if supersample == 0 || supersample > MAX_SUPERSAMPLE {
+            return Err(Error::InvalidOption(format!("Supersample must be between 1 and {}: {}", MAX_SUPERSAMPLE, supersample)));
         }
+        self.supersample = supersample;
+        return Ok(());
+    }
+
+    // ドット絵モード: アンチエイリアスを無効にし、グリフを整数のピクセルに揃える
+    pub fn set_pixel_art(&mut self, pixel_art: bool) {
+        self.pixel_art = pixel_art;
+        self.shape.set_anti_alias(!pixel_art);
+    }
+
+    pub fn set_palette_size(&mut self, palette_size: u32) -> Result<(), Error> {
+        if !(2..=MAX_PALETTE_SIZE).contains(&palette_size) {
+            return Err(Error::InvalidOption(format!("Palette size must be between 2 and {}: {}", MAX_PALETTE_SIZE, palette_size)));
+        }
+        self.palette_size = Some(palette_size);
+        return Ok(());
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: u32) -> Result<(), Error> {
+        if max_bytes == 0 {
+            return Err(Error::InvalidOption("Max bytes must be greater than 0: 0".to_string()));
+        }
+        self.max_bytes = Some(max_bytes);
+        return Ok(());
+    }
+
+    // 描画するサーフェスの画素数の上限 (スーパーサンプリング後の大きさで判定する。サーバーなどで使用する)
+    pub fn set_max_pixels(&mut self, max_pixels: u64) {
+        self.max_pixels = Some(max_pixels);
+    }
+
+    // 最後に生成した画像をmaxBytesに収めるために行った調整 (例: "quality=80")
+    pub fn get_adjustments(&self) -> &[String] {
+        return &self.adjustments;
+    }
 
-        self.typeface = tf.unwrap();
+    // 最後に生成した画像で、エラーにはしなかった問題 (alphaLossがwarnの場合の透明部分の消失など)
+    pub fn get_warnings(&self) -> &[String] {
+        return &self.warnings;
     }
 
-    pub fn set_format(&mut self, format: SkEncodedImageFormat) -> Result<(), String> {
+    // 最後に生成した画像の形式 ("png" / "jpeg"。maxBytesによりformatから変わることがある)
+    pub fn get_output_format(&self) -> &'static str {
+        return utils::image_format_name(self.output_format);
+    }
+
+    pub fn set_format(&mut self, format: SkEncodedImageFormat) -> Result<(), Error> {
         if format != SkEncodedImageFormat::PNG && format != SkEncodedImageFormat::JPEG {
-            return Err(format!("Invalid image format: {:?}", format));
+            return Err(Error::InvalidFormat(format!("Invalid image format: {:?}", format)));
         }
         self.format = format;
         return Ok(());
     }
 
-    pub fn set_format_by_string(&mut self, format: String) -> Result<(), String> {
+    pub fn set_format_by_string(&mut self, format: String) -> Result<(), Error> {
         let result = utils::parse_image_format(format);
         if result.is_err() {
             return Err(result.unwrap_err());
@@ -170,27 +580,366 @@ impl Generator {
         return Ok(());
     }
 
+    pub fn set_matte_color(&mut self, matte_color: String) -> Result<(), Error> {
+        let result = utils::parse_color_code(matte_color);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.matte_color = result.unwrap();
+        return Ok(());
+    }
+
+    pub fn set_alpha_loss(&mut self, alpha_loss: AlphaLoss) {
+        self.alpha_loss = alpha_loss;
+    }
+
+    pub fn set_alpha_loss_by_string(&mut self, alpha_loss: String) -> Result<(), Error> {
+        let result = utils::parse_alpha_loss(alpha_loss);
+        if result.is_err() {
+            return Err(result.unwrap_err());
+        }
+        self.alpha_loss = result.unwrap();
+        return Ok(());
+    }
+
     pub fn set_quality(&mut self, quality: u32) {
         self.quality = quality;
     }
 
-    pub fn generate(&mut self) -> Result<SkData, String> {
-        let line_height = self.height / self.texts.len() as f32;
+    // オプションをまとめて適用する
+    pub fn set_options(&mut self, options: Options) -> Result<(), Error> {
+        if let Some(width) = options.width {
+            self.set_width(width);
+        }
+
+        if let Some(height) = options.height {
+            self.set_height(height);
+        }
+
+        if let Some(flexible_width) = options.flexible_width {
+            self.set_flexible_width(flexible_width);
+        }
+
+        if let Some(color) = options.color {
+            let result = self.set_color(color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(color_palette) = options.color_palette {
+            let result = self.set_color_palette(color_palette);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(fill_image) = options.fill_image {
+            let result = self.set_fill_image(&fill_image);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(fill_image_tile_mode) = options.fill_image_tile_mode {
+            let result = self.set_fill_image_tile_mode_by_string(fill_image_tile_mode);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(fill_image_scale) = options.fill_image_scale {
+            let result = self.set_fill_image_scale(fill_image_scale);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(background_color) = options.background_color {
+            let result = self.set_background_color(background_color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(text_align) = options.text_align {
+            let result = self.set_text_align_by_string(text_align);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(text_size_fixed) = options.text_size_fixed {
+            self.set_text_size_fixed(text_size_fixed);
+        }
+
+        if let Some(disable_stretch) = options.disable_stretch {
+            self.set_disable_stretch(disable_stretch);
+        }
+
+        if let Some(disable_outline) = options.disable_outline {
+            self.set_disable_outline(disable_outline);
+        }
+
+        if let Some(outline_width) = options.outline_width {
+            self.set_outline_width(outline_width);
+        }
+
+        if let Some(outline_color) = options.outline_color {
+            let result = self.set_outline_color(outline_color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(shape) = options.shape {
+            let result = self.set_shape_by_string(shape);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(shape_color) = options.shape_color {
+            let result = self.set_shape_color(shape_color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(shape_border_width) = options.shape_border_width {
+            self.set_shape_border_width(shape_border_width);
+        }
+
+        if let Some(shape_border_color) = options.shape_border_color {
+            let result = self.set_shape_border_color(shape_border_color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(shape_radius) = options.shape_radius {
+            self.set_shape_radius(shape_radius);
+        }
+
+        if let Some(shape_padding) = options.shape_padding {
+            self.set_shape_padding(shape_padding);
+        }
+
+        if let Some(background_image) = options.background_image {
+            let result = self.set_background_image(&background_image);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(background_image_fit) = options.background_image_fit {
+            let result = self.set_background_image_fit_by_string(background_image_fit);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(overlay_images) = options.overlay_images {
+            for overlay in overlay_images {
+                let result = self.add_overlay_image(&overlay.image, overlay.x as f32, overlay.y as f32, overlay.width, overlay.height);
+                if result.is_err() {
+                    return Err(result.unwrap_err());
+                }
+            }
+        }
+
+        if let Some(trim) = options.trim {
+            let result = self.set_trim_by_string(trim);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(trim_padding) = options.trim_padding {
+            self.set_trim_padding(trim_padding);
+        }
+
+        if let Some(typeface_file) = options.typeface_file {
+            let result = self.set_typeface_file(typeface_file, options.typeface_index.unwrap_or(0));
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(typeface_name) = options.typeface_name {
+            self.set_typeface_name(typeface_name);
+        }
+
+        if let Some(typeface_weight) = options.typeface_weight {
+            self.set_typeface_weight(typeface_weight);
+        }
+
+        if let Some(typeface_width) = options.typeface_width {
+            let result = self.set_typeface_width_by_string(typeface_width);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(typeface_slant) = options.typeface_slant {
+            let result = self.set_typeface_slant_by_string(typeface_slant);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(typeface_variations) = options.typeface_variations {
+            for (axis, value) in typeface_variations {
+                let result = self.set_typeface_variation(axis, value);
+                if result.is_err() {
+                    return Err(result.unwrap_err());
+                }
+            }
+        }
+
+        if let Some(fit_width_axis) = options.fit_width_axis {
+            self.set_fit_width_axis(fit_width_axis);
+        }
+
+        if let Some(opentype_features) = options.opentype_features {
+            for (tag, value) in opentype_features {
+                let result = self.set_opentype_feature(tag, value);
+                if result.is_err() {
+                    return Err(result.unwrap_err());
+                }
+            }
+        }
+
+        if let Some(letter_spacing) = options.letter_spacing {
+            let result = self.set_letter_spacing_by_string(letter_spacing);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(fit_precision) = options.fit_precision {
+            let result = self.set_fit_precision(fit_precision);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(font_hinting) = options.font_hinting {
+            let result = self.set_font_hinting_by_string(font_hinting);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(font_edging) = options.font_edging {
+            let result = self.set_font_edging_by_string(font_edging);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(font_subpixel) = options.font_subpixel {
+            self.set_font_subpixel(font_subpixel);
+        }
+
+        if let Some(supersample) = options.supersample {
+            let result = self.set_supersample(supersample);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(pixel_art) = options.pixel_art {
+            self.set_pixel_art(pixel_art);
+        }
+
+        if let Some(palette_size) = options.palette_size {
+            let result = self.set_palette_size(palette_size);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(max_bytes) = options.max_bytes {
+            let result = self.set_max_bytes(max_bytes);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(format) = options.format {
+            let result = self.set_format_by_string(format);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(matte_color) = options.matte_color {
+            let result = self.set_matte_color(matte_color);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(alpha_loss) = options.alpha_loss {
+            let result = self.set_alpha_loss_by_string(alpha_loss);
+            if result.is_err() {
+                return Err(result.unwrap_err());
+            }
+        }
+
+        if let Some(quality) = options.quality {
+            self.set_quality(quality);
+        }
+
+        return Ok(());
+    }
+
+    pub fn generate(&mut self) -> Result<SkData, Error> {
+        // 前回の生成結果の情報をリセット
+        self.crop_rect = None;
+        self.adjustments = Vec::new();
+        self.warnings = Vec::new();
+        self.output_format = self.format;
+
+        // 背景の図形がある場合は図形に内接する領域にテキストを収める
+        let content = self.shape.content_rect(self.width, self.height);
+        let line_height = content.height() / self.texts.len() as f32;
+        let typeface = self.resolve_typeface();
+        let text_color = self.resolve_color();
+        let outline_color = self.resolve_outline_color();
+        let fill_shader = self.fill_image.as_ref().and_then(|fill_image| image::fill_shader(fill_image, self.fill_image_tile_mode, self.fill_image_scale));
+
+        // 要求されたスタイルがフォントに無い場合は疑似ボールド・疑似イタリックで補う
+        let style = typeface.font_style();
+        let fake_bold = self.typeface_weight >= SkFontWeight::SEMI_BOLD && style.weight() < SkFontWeight::SEMI_BOLD;
+        let fake_italic = self.typeface_slant != SkFontSlant::Upright && style.slant() == SkFontSlant::Upright;
 
         // 行ボックスを作成
         let mut lines = Vec::new();
         for text in &self.texts {
             let mut line = Line::new();
-            line.set_width(self.width);
+            line.set_width(content.width());
             line.set_line_height(line_height);
             line.set_text(text.to_string());
-            line.set_typeface(self.typeface.clone());
+            line.set_typeface(typeface.clone());
+            line.set_fake_bold(fake_bold);
+            line.set_fake_italic(fake_italic);
+            line.set_fit_width_axis(self.fit_width_axis);
+            line.set_features(self.opentype_features.clone());
+            line.set_letter_spacing(self.letter_spacing);
+            line.set_fit_precision(self.fit_precision);
             line.set_text_align(self.text_align);
-            line.set_color(self.color);
+            line.set_color(text_color);
+            line.set_fill_shader(fill_shader.clone());
             line.set_disable_stretch(self.disable_stretch);
             line.set_disable_outline(self.disable_outline);
             line.set_outline_width(self.outline_width);
-            line.set_outline_color(self.outline_color);
+            line.set_outline_color(outline_color);
+            line.set_hinting(self.font_hinting);
+            line.set_edging(self.font_edging);
+            line.set_subpixel(self.font_subpixel);
+            line.set_anti_alias(!self.pixel_art);
 
             // 高さ・幅を計測
             line.measure(None);
@@ -198,10 +947,10 @@ impl Generator {
             lines.push(line);
         }
 
-        // フレキシブルモード: 最大widthで再計算
-        if self.flexible_width {
+        // フレキシブルモード: 最大widthで再計算 (テキストが無ければ幅は変えない)
+        if self.flexible_width && !lines.is_empty() {
             let max_width = lines.iter().map(|line| line.get_raw_bounds_width()).max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap();
-            self.width = max_width;
+            self.width = if self.shape.is_enabled() { self.shape.outer_width(max_width, self.height) } else { max_width };
             
             for line in &mut lines {
                 line.set_width(max_width);
@@ -217,27 +966,326 @@ impl Generator {
             }
         }
 
-        let surface_prepare = SkSurfaces::raster_n32_premul(ISize::new(self.width as i32, self.height as i32));
+        if self.width > MAX_IMAGE_SIZE || self.height > MAX_IMAGE_SIZE {
+            return Err(Error::LimitsExceeded(format!("Image size must be {} pixels or less: {}x{}", MAX_IMAGE_SIZE, self.width, self.height)));
+        }
+
+        // スーパーサンプリング: supersample倍の解像度で描画し、最後に縮小する (縮小で輪郭がぼけるため、ドット絵モードでは行わない)
+        let (width, height) = (self.width as i32, self.height as i32);
+        let scale = if self.pixel_art { 1 } else { self.supersample as i32 };
+        if (width * scale) as f32 > MAX_IMAGE_SIZE || (height * scale) as f32 > MAX_IMAGE_SIZE {
+            return Err(Error::LimitsExceeded(format!("Supersampled image size must be {} pixels or less: {}x{}", MAX_IMAGE_SIZE, width * scale, height * scale)));
+        }
+        if let Some(max_pixels) = self.max_pixels {
+            let pixels = (width * scale) as u64 * (height * scale) as u64;
+            if pixels > max_pixels {
+                return Err(Error::LimitsExceeded(format!("Image must be {} pixels or less: {}x{}", max_pixels, width * scale, height * scale)));
+            }
+        }
+
+        let surface_prepare = SkSurfaces::raster_n32_premul(ISize::new(width * scale, height * scale));
         if surface_prepare.is_none() {
-            return Err("Failed to create surface.".to_string());
+            return Err(Error::SurfaceCreationFailed);
         }
 
         let mut surface = surface_prepare.unwrap();
         let mut canvas = surface.canvas();
         canvas.clear(self.background_color);
+        canvas.scale((scale as f32, scale as f32));
+
+        // 背景画像を描画
+        if let Some(background_image) = &self.background_image {
+            image::draw_background(&mut canvas, background_image, self.background_image_fit, self.width, self.height);
+        }
+
+        // 背景の図形を描画
+        if self.shape.is_enabled() {
+            self.shape.draw(&mut canvas, self.width, self.height);
+        }
 
         // テキストを描画
+        let content = self.shape.content_rect(self.width, self.height);
+        canvas.save();
+        canvas.translate((content.left, content.top));
         for (i, line) in lines.iter_mut().enumerate() {
             line.draw(&mut canvas, line_height * i as f32);
         }
+        canvas.restore();
+
+        // 重ねる画像を描画
+        for overlay in &self.overlay_images {
+            image::draw_overlay(&mut canvas, overlay);
+        }
+
+        let mut snapshot = surface.image_snapshot();
+        if scale > 1 {
+            let downsampled = image::downsample(&snapshot, width, height);
+            if downsampled.is_none() {
+                return Err(Error::SurfaceCreationFailed);
+            }
+            snapshot = downsampled.unwrap();
+        }
+
+        // 色数を制限する
+        if let Some(palette_size) = self.palette_size {
+            let quantized = image::quantize(&snapshot, palette_size as usize);
+            if quantized.is_none() {
+                return Err(Error::SurfaceCreationFailed);
+            }
+            snapshot = quantized.unwrap();
+        }
+
+        // 透明な余白を切り取る (透明でない画素が無い場合はそのまま)
+        if self.trim != Trim::None {
+            let (width, height) = (snapshot.width() as u32, snapshot.height() as u32);
+            let rect = match image::ink_bounds(&snapshot) {
+                Some(bounds) => image::trim_rect(bounds, self.trim, self.trim_padding, width, height, MAX_IMAGE_SIZE as u32),
+                None => Some(CropRect { x: 0, y: 0, width, height }),
+            };
+            if rect.is_none() {
+                return Err(Error::LimitsExceeded(format!("Trimmed image size must be {} pixels or less (trim_padding: {})", MAX_IMAGE_SIZE, self.trim_padding)));
+            }
+
+            let rect = rect.unwrap();
+
+            let cropped = image::crop(&snapshot, rect);
+            if cropped.is_none() {
+                return Err(Error::SurfaceCreationFailed);
+            }
+            snapshot = cropped.unwrap();
+            self.crop_rect = Some(rect);
+        }
 
         // エンコード
-        let image = surface.image_snapshot();
-        let data = image.encode(None, self.format, self.quality);
+        // JPEGはアルファを持たないため、透明部分をマット色の上に合成してからエンコードする
+        if self.format == SkEncodedImageFormat::JPEG && !image::is_opaque(&snapshot) {
+            let message = "The image has transparent pixels, which are lost in JPEG.";
+            match self.alpha_loss {
+                AlphaLoss::Ignore => {},
+                AlphaLoss::Warn => self.warnings.push(format!("{} They are composited over the matte color.", message)),
+                AlphaLoss::Error => return Err(Error::AlphaLoss(message.to_string())),
+            }
+
+            let flattened = image::flatten(&snapshot, self.matte_color);
+            if flattened.is_none() {
+                return Err(Error::SurfaceCreationFailed);
+            }
+            snapshot = flattened.unwrap();
+        }
+
+        let data = snapshot.encode(None, self.format, self.quality);
         if data.is_none() {
-            return Err("Failed to encode image.".to_string());
+            return Err(Error::EncodeFailed);
+        }
+
+        let data = data.unwrap();
+        if let Some(max_bytes) = self.max_bytes {
+            if data.size() > max_bytes as usize {
+                return self.fit_to_budget(&snapshot, max_bytes as usize, data.size());
+            }
         }
 
-        return Ok(data.unwrap());
+        return Ok(data);
     }
-}
\ No newline at end of file
+
+    // maxBytesに収まるまで、PNGは色数を減らし、それでも収まらず透明な部分が無ければJPEGに切り替えて品質を下げる
+    fn fit_to_budget(&mut self, snapshot: &SkImage, max_bytes: usize, size: usize) -> Result<SkData, Error> {
+        let mut smallest = size;
+
+        if self.format == SkEncodedImageFormat::PNG {
+            let current = self.palette_size.unwrap_or(u32::MAX);
+            for palette_size in BUDGET_PALETTE_SIZES.iter().filter(|palette_size| **palette_size < current) {
+                let data = image::quantize(snapshot, *palette_size as usize).and_then(|quantized| quantized.encode(None, SkEncodedImageFormat::PNG, self.quality));
+                if data.is_none() {
+                    return Err(Error::EncodeFailed);
+                }
+
+                let data = data.unwrap();
+                if data.size() <= max_bytes {
+                    self.adjustments.push(format!("palette_size={}", palette_size));
+                    return Ok(data);
+                }
+                smallest = smallest.min(data.size());
+            }
+
+            // 透明な部分が失われるため、不透明な画像のみJPEGに切り替える
+            if !image::is_opaque(snapshot) {
+                return Err(Error::LimitsExceeded(format!("Image could not be encoded within {} bytes: {} bytes", max_bytes, smallest)));
+            }
+            self.adjustments.push("format=jpeg".to_string());
+            self.output_format = SkEncodedImageFormat::JPEG;
+        }
+
+        // JPEGの品質を下げる (PNGから切り替えた場合は指定された品質から試す)
+        let mut qualities: Vec<u32> = BUDGET_QUALITIES.iter().copied().filter(|quality| *quality < self.quality).collect();
+        if self.output_format != self.format {
+            qualities.insert(0, self.quality);
+        }
+
+        for quality in qualities {
+            let data = snapshot.encode(None, SkEncodedImageFormat::JPEG, quality);
+            if data.is_none() {
+                return Err(Error::EncodeFailed);
+            }
+
+            let data = data.unwrap();
+            if data.size() <= max_bytes {
+                if quality != self.quality {
+                    self.adjustments.push(format!("quality={}", quality));
+                }
+                return Ok(data);
+            }
+            smallest = smallest.min(data.size());
+        }
+
+        return Err(Error::LimitsExceeded(format!("Image could not be encoded within {} bytes: {} bytes", max_bytes, smallest)));
+    }
+
+    // 現在のオプションでの文字色・縁取り・背景のコントラスト比を計算する
+    pub fn contrast_report(&self) -> ContrastReport {
+        let text_color = self.resolve_color();
+        let outline_color = self.resolve_outline_color();
+        let background = color::opaque_background(self.resolve_backdrop());
+
+        let text_background = background.map(|background| color::contrast_ratio(text_color, background));
+        let (outline_text, outline_background) = if self.disable_outline {
+            (None, None)
+        } else {
+            (Some(color::contrast_ratio(outline_color, text_color)), background.map(|background| color::contrast_ratio(outline_color, background)))
+        };
+
+        // 縁取りがある場合は縁取りが文字と背景の両方から区別できれば十分とする
+        let sufficient = match outline_text {
+            Some(outline_text) => outline_text >= color::MIN_CONTRAST_RATIO && outline_background.map_or(true, |ratio| ratio >= color::MIN_CONTRAST_RATIO),
+            None => text_background.map_or(true, |ratio| ratio >= color::MIN_CONTRAST_RATIO),
+        };
+
+        return ContrastReport {
+            outline_color: color::to_hex(outline_color),
+            text_background,
+            outline_text,
+            outline_background,
+            sufficient,
+        };
+    }
+
+    fn resolve_color(&self) -> SkColor {
+        if self.color_hash {
+            return color::hash_color(&self.texts.join("\n"), &self.color_palette);
+        }
+
+        return self.color;
+    }
+
+    // 文字の背後の色 (塗りのあるシェイプなら背景色の上に重ねたシェイプの塗り)
+    fn resolve_backdrop(&self) -> SkColor {
+        if self.shape.is_enabled() && self.shape.is_filled() {
+            return color::composite(self.shape.get_color(), self.background_color);
+        }
+
+        return self.background_color;
+    }
+
+    fn resolve_outline_color(&self) -> SkColor {
+        if self.outline_color_auto {
+            return color::auto_outline_color(self.resolve_color(), self.resolve_backdrop());
+        }
+
+        if self.outline_color_hash {
+            return color::hash_outline_color(&self.texts.join("\n"), &self.color_palette, self.resolve_color(), self.resolve_backdrop());
+        }
+
+        return self.outline_color;
+    }
+
+    fn resolve_typeface(&self) -> SkTypeface {
+        let style = SkFontStyle::new(self.typeface_weight, self.typeface_width, self.typeface_slant);
+        let mut typeface = self.typeface.clone();
+
+        // フォント名指定: 登録済みの別名を優先し、無ければインストール済みのフォントから要求スタイルに最も近いものを選択
+        if let Some(name) = &self.typeface_name {
+            let tf = font::get_registered_typeface(name).or_else(|| SkTypeface::from_name(name, style));
+            if tf.is_some() {
+                typeface = tf.unwrap();
+            }
+        }
+
+        // バリアブルフォント: 軸の値を適用
+        if !self.typeface_variations.is_empty() {
+            let args = SkFontArguments::new().set_variation_design_position(SkVariationPosition {
+                coordinates: &self.typeface_variations,
+            });
+            let tf = typeface.clone_with_arguments(&args);
+            if tf.is_some() {
+                typeface = tf.unwrap();
+            }
+        }
+
+        return typeface;
+    }
+}
+
+// テキストとオプションから絵文字を生成し、エンコード済みの画像を返す
+pub fn generate(text: String, options: Options) -> Result<Vec<u8>, Error> {
+    let mut emoji = Generator::new();
+    emoji.set_texts(text);
+
+    let result = emoji.set_options(options);
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    let result = emoji.generate();
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    return Ok(result.unwrap().as_bytes().to_vec());
+}
+
+// 生成した画像と、生成時に決まった情報
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub data: Vec<u8>,
+    pub crop_rect: Option<CropRect>,
+    pub format: String,
+    pub adjustments: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+// 画像と合わせて切り取った範囲などを返す
+pub fn generate_with_info(text: String, options: Options) -> Result<Output, Error> {
+    let mut emoji = Generator::new();
+    emoji.set_texts(text);
+
+    let result = emoji.set_options(options);
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    let result = emoji.generate();
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    return Ok(Output {
+        data: result.unwrap().as_bytes().to_vec(),
+        crop_rect: emoji.get_crop_rect(),
+        format: emoji.get_output_format().to_string(),
+        adjustments: emoji.get_adjustments().to_vec(),
+        warnings: emoji.get_warnings().to_vec(),
+    });
+}
+
+// テキストとオプションから生成される絵文字のコントラスト比を計算する
+pub fn check_contrast(text: String, options: Options) -> Result<ContrastReport, Error> {
+    let mut emoji = Generator::new();
+    emoji.set_texts(text);
+
+    let result = emoji.set_options(options);
+    if result.is_err() {
+        return Err(result.unwrap_err());
+    }
+
+    return Ok(emoji.contrast_report());
+}
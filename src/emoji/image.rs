@@ -0,0 +1,272 @@
+use super::error::Error;
+use skia_safe::scalar as SkScalar;
+use skia_safe::Rect as SkRect;
+use skia_safe::Data as SkData;
+use skia_safe::Image as SkImage;
+use skia_safe::Canvas as SkCanvas;
+use skia_safe::TileMode as SkTileMode;
+use skia_safe::SamplingOptions as SkSamplingOptions;
+use skia_safe::FilterMode as SkFilterMode;
+use skia_safe::MipmapMode as SkMipmapMode;
+use skia_safe::Matrix as SkMatrix;
+use skia_safe::Shader as SkShader;
+use skia_safe::Color as SkColor;
+use skia_safe::ISize;
+use skia_safe::surfaces as SkSurfaces;
+use std::collections::HashMap;
+use skia_safe::paint::Paint as SkPaint;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageFit {
+    Cover,
+    Contain,
+    Stretch,
+    Tile,
+}
+
+// アルファを持たない形式でエンコードするときに透明部分が失われる場合の扱い
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlphaLoss {
+    Ignore,
+    Warn,
+    Error,
+}
+
+// 透明な余白の切り取り方
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Trim {
+    None,
+    Bounds,
+    KeepHeight,
+    KeepAspect,
+}
+
+// 切り取った範囲 (元の画像の座標。余白を付けた場合は画像の外にはみ出すことがある)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CropRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// 画像の上に重ねて描画する画像 (幅・高さの片方のみ指定された場合は縦横比を保つ)
+#[derive(Clone)]
+pub struct Overlay {
+    pub image: SkImage,
+    pub x: SkScalar,
+    pub y: SkScalar,
+    pub width: Option<SkScalar>,
+    pub height: Option<SkScalar>,
+}
+
+pub fn decode_image(data: &[u8]) -> Result<SkImage, Error> {
+    let image = SkImage::from_encoded(SkData::new_copy(data));
+    if image.is_none() {
+        return Err(Error::ImageDecodeFailed("Failed to decode image.".to_string()));
+    }
+
+    return Ok(image.unwrap());
+}
+
+fn sampling() -> SkSamplingOptions {
+    return SkSamplingOptions::new(SkFilterMode::Linear, SkMipmapMode::Linear);
+}
+
+pub fn draw_background(canvas: &mut SkCanvas, image: &SkImage, fit: ImageFit, width: SkScalar, height: SkScalar) {
+    let mut paint = SkPaint::default();
+    paint.set_anti_alias(true);
+
+    let image_width = image.width() as SkScalar;
+    let image_height = image.height() as SkScalar;
+
+    let dst = match fit {
+        ImageFit::Stretch => SkRect::from_wh(width, height),
+        ImageFit::Cover | ImageFit::Contain => {
+            // cover: 画像全体を覆う最小の倍率、contain: 画像全体が収まる最大の倍率
+            let scale = if fit == ImageFit::Cover {
+                (width / image_width).max(height / image_height)
+            } else {
+                (width / image_width).min(height / image_height)
+            };
+            let (dst_width, dst_height) = (image_width * scale, image_height * scale);
+            SkRect::from_xywh((width - dst_width) / 2.0, (height - dst_height) / 2.0, dst_width, dst_height)
+        },
+        ImageFit::Tile => {
+            let shader = image.to_shader((SkTileMode::Repeat, SkTileMode::Repeat), sampling(), None);
+            paint.set_shader(shader);
+            canvas.draw_rect(SkRect::from_wh(width, height), &paint);
+            return;
+        },
+    };
+
+    canvas.draw_image_rect_with_sampling_options(image, None, dst, sampling(), &paint);
+}
+
+pub fn draw_overlay(canvas: &mut SkCanvas, overlay: &Overlay) {
+    let image_width = overlay.image.width() as SkScalar;
+    let image_height = overlay.image.height() as SkScalar;
+
+    let (width, height) = match (overlay.width, overlay.height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, image_height * width / image_width),
+        (None, Some(height)) => (image_width * height / image_height, height),
+        (None, None) => (image_width, image_height),
+    };
+
+    let mut paint = SkPaint::default();
+    paint.set_anti_alias(true);
+    canvas.draw_image_rect_with_sampling_options(&overlay.image, None, SkRect::from_xywh(overlay.x, overlay.y, width, height), sampling(), &paint);
+}
+
+// 文字の塗りつぶしに使う画像のシェーダー (画像の左上を原点として、scale倍して並べる)
+pub fn fill_shader(image: &SkImage, tile_mode: SkTileMode, scale: SkScalar) -> Option<SkShader> {
+    let matrix = SkMatrix::scale((scale, scale));
+    return image.to_shader((tile_mode, tile_mode), sampling(), &matrix);
+}
+
+// 画像が完全に不透明か (画素を直接参照できない画像はラスター画像に変換して調べ、それも失敗すれば不透明とはみなさない)
+pub fn is_opaque(image: &SkImage) -> bool {
+    if let Some(pixmap) = image.peek_pixels() {
+        return pixmap.compute_is_opaque();
+    }
+
+    let raster = image.to_raster_image(None);
+    if raster.is_none() {
+        return false;
+    }
+
+    return raster.unwrap().peek_pixels().is_some_and(|pixmap| pixmap.compute_is_opaque());
+}
+
+// 透明部分をmatteの色の上に合成した画像
+pub fn flatten(image: &SkImage, matte: SkColor) -> Option<SkImage> {
+    let mut surface = SkSurfaces::raster_n32_premul(ISize::new(image.width(), image.height()))?;
+    let canvas = surface.canvas();
+    canvas.clear(matte.with_a(255));
+    canvas.draw_image(image, (0.0, 0.0), None);
+    return Some(surface.image_snapshot());
+}
+
+// 透明でない画素を含む最小の範囲 (すべて透明ならNone)
+pub fn ink_bounds(image: &SkImage) -> Option<CropRect> {
+    let pixmap = image.peek_pixels()?;
+    let bytes = pixmap.bytes()?;
+    let row_bytes = pixmap.row_bytes();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    // N32はRGBA・BGRAのどちらでもアルファが4バイト目にある
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for y in 0..height {
+        let row = &bytes[y * row_bytes..y * row_bytes + width * 4];
+        for x in 0..width {
+            if row[x * 4 + 3] != 0 {
+                left = left.min(x);
+                right = right.max(x + 1);
+                top = top.min(y);
+                bottom = bottom.max(y + 1);
+            }
+        }
+    }
+
+    if left >= right || top >= bottom {
+        return None;
+    }
+
+    return Some(CropRect { x: left as i32, y: top as i32, width: (right - left) as u32, height: (bottom - top) as u32 });
+}
+
+// インクの範囲に余白を付け、切り取り方に応じて広げた範囲 (幅・高さがmax_sizeを超える場合はNone)
+pub fn trim_rect(bounds: CropRect, trim: Trim, padding: u32, width: u32, height: u32, max_size: u32) -> Option<CropRect> {
+    // 大きな余白でも桁あふれしないようi64で計算する
+    let padding = padding as i64;
+    let (x, y) = (bounds.x as i64 - padding, bounds.y as i64 - padding);
+    let (w, h) = (bounds.width as i64 + padding * 2, bounds.height as i64 + padding * 2);
+
+    let (x, y, w, h) = match trim {
+        Trim::None => (0, 0, width as i64, height as i64),
+        Trim::Bounds => (x, y, w, h),
+        Trim::KeepHeight => (x, 0, w, height as i64),
+        Trim::KeepAspect => {
+            // 元の画像と同じ縦横比になるよう、インクの中心を基準に短い方の辺を広げる
+            let aspect = width as f64 / height as f64;
+            let (new_w, new_h) = if (w as f64) < h as f64 * aspect {
+                ((h as f64 * aspect).round() as i64, h)
+            } else {
+                (w, (w as f64 / aspect).round() as i64)
+            };
+            (x - (new_w - w) / 2, y - (new_h - h) / 2, new_w, new_h)
+        },
+    };
+
+    if w > max_size as i64 || h > max_size as i64 {
+        return None;
+    }
+
+    return Some(CropRect { x: x as i32, y: y as i32, width: w as u32, height: h as u32 });
+}
+
+pub fn crop(image: &SkImage, rect: CropRect) -> Option<SkImage> {
+    let mut surface = SkSurfaces::raster_n32_premul(ISize::new(rect.width as i32, rect.height as i32))?;
+    let canvas = surface.canvas();
+    canvas.clear(SkColor::TRANSPARENT);
+    canvas.draw_image(image, (-rect.x as f32, -rect.y as f32), None);
+    return Some(surface.image_snapshot());
+}
+
+// スーパーサンプリングで描画した画像を出力サイズに縮小する (ミップマップを使い、縮小率が大きくてもジャギーが出ないようにする)
+pub fn downsample(image: &SkImage, width: i32, height: i32) -> Option<SkImage> {
+    let mut surface = SkSurfaces::raster_n32_premul(ISize::new(width, height))?;
+    let canvas = surface.canvas();
+    canvas.clear(SkColor::TRANSPARENT);
+    canvas.draw_image_rect_with_sampling_options(image, None, SkRect::from_iwh(width, height), sampling(), &SkPaint::default());
+    return Some(surface.image_snapshot());
+}
+
+// 画像の色数をcolors色以下に減らす (出現数の多い色をパレットとし、各画素を最も近いパレットの色に置き換える)
+pub fn quantize(image: &SkImage, colors: usize) -> Option<SkImage> {
+    let pixmap = image.peek_pixels()?;
+    let row_bytes = pixmap.row_bytes();
+    let mut bytes = pixmap.bytes()?.to_vec();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    let pixel = |bytes: &[u8], offset: usize| -> [u8; 4] {
+        return [bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]];
+    };
+
+    let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            *counts.entry(pixel(&bytes, y * row_bytes + x * 4)).or_insert(0) += 1;
+        }
+    }
+
+    if counts.len() <= colors {
+        return Some(image.clone());
+    }
+
+    // 出現数が同じ場合も結果が変わらないよう、色の値でも並べる
+    let mut sorted: Vec<([u8; 4], usize)> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let palette: Vec<[u8; 4]> = sorted.iter().take(colors).map(|(color, _)| *color).collect();
+
+    let distance = |a: &[u8; 4], b: &[u8; 4]| -> u32 {
+        return a.iter().zip(b.iter()).map(|(a, b)| (*a as i32 - *b as i32).pow(2) as u32).sum();
+    };
+
+    let mut nearest: HashMap<[u8; 4], [u8; 4]> = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            let offset = y * row_bytes + x * 4;
+            let color = pixel(&bytes, offset);
+            let mapped = *nearest.entry(color).or_insert_with(|| *palette.iter().min_by_key(|entry| distance(entry, &color)).unwrap());
+            bytes[offset..offset + 4].copy_from_slice(&mapped);
+        }
+    }
+
+    let mut surface = SkSurfaces::raster_n32_premul(ISize::new(width as i32, height as i32))?;
+    if !surface.canvas().write_pixels(pixmap.info(), &bytes, row_bytes, (0, 0)) {
+        return None;
+    }
+    return Some(surface.image_snapshot());
+}
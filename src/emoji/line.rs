@@ -6,16 +6,39 @@ use skia_safe::scalar as SkScalar;
 use skia_safe::Rect as SkRect;
 use skia_safe::paint::Paint as SkPaint;
 use skia_safe::Font as SkFont;
+use skia_safe::FontHinting as SkFontHinting;
+use skia_safe::font::Edging as SkFontEdging;
 use skia_safe::colors as SkColors;
 use skia_safe::TextEncoding as SkTextEncoding;
 use skia_safe::Canvas as SkCanvas;
+use skia_safe::Shader as SkShader;
 use skia_safe::utils::text_utils as SkTextUtils;
 use skia_safe::paint::Style as SkStyle;
+use skia_safe::FourByteTag as SkFourByteTag;
+use skia_safe::FontArguments as SkFontArguments;
+use skia_safe::font_arguments::VariationPosition as SkVariationPosition;
+use skia_safe::font_arguments::variation_position::Coordinate as SkCoordinate;
+use super::shaping;
+
+const WIDTH_AXIS: SkFourByteTag = SkFourByteTag::from_chars('w', 'd', 't', 'h');
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LetterSpacing {
+    Absolute(SkScalar),
+    Em(SkScalar),
+}
 
 pub struct MeasureSpec {
     text_size: SkScalar,
     text_scale_x: SkScalar,
     bounds: SkRect,
+    typeface: Option<SkTypeface>,
+}
+
+impl Default for MeasureSpec {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MeasureSpec {
@@ -24,6 +47,7 @@ impl MeasureSpec {
             text_size: 128.0,
             text_scale_x: 1.0,
             bounds: SkRect::new(0.0, 0.0, 128.0, 128.0),
+            typeface: None,
         }
     }
 }
@@ -33,13 +57,30 @@ pub struct Line {
     width: SkScalar,
     line_height: SkScalar,
     typeface: SkTypeface,
+    fake_bold: bool,
+    fake_italic: bool,
+    fit_width_axis: bool,
+    features: Vec<(SkFourByteTag, u32)>,
+    letter_spacing: LetterSpacing,
+    fit_precision: SkScalar,
     text_align: SkTextAlign,
     color: SkColor,
+    fill_shader: Option<SkShader>,
     disable_stretch: bool,
     spec: MeasureSpec,
     disable_outline: bool,
     outline_width: SkScalar,
     outline_color: SkColor,
+    hinting: SkFontHinting,
+    edging: SkFontEdging,
+    subpixel: bool,
+    anti_alias: bool,
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Line {
@@ -49,13 +90,24 @@ impl Line {
             width: 128.0,
             line_height: 128.0,
             typeface: SkTypeface::default(),
+            fake_bold: false,
+            fake_italic: false,
+            fit_width_axis: false,
+            features: Vec::new(),
+            letter_spacing: LetterSpacing::Absolute(0.0),
+            fit_precision: 0.5,
             text_align: SkTextAlign::Center,
             color: SkColor::BLACK,
+            fill_shader: None,
             disable_stretch: false,
             spec: MeasureSpec::new(),
             disable_outline: false,
             outline_width: 8.0,
             outline_color: SkColors::WHITE.to_color(),
+            hinting: SkFontHinting::Normal,
+            edging: SkFontEdging::AntiAlias,
+            subpixel: false,
+            anti_alias: true,
         }
     }
 
@@ -75,6 +127,30 @@ impl Line {
         self.typeface = typeface;
     }
 
+    pub fn set_fake_bold(&mut self, fake_bold: bool) {
+        self.fake_bold = fake_bold;
+    }
+
+    pub fn set_fake_italic(&mut self, fake_italic: bool) {
+        self.fake_italic = fake_italic;
+    }
+
+    pub fn set_fit_width_axis(&mut self, fit_width_axis: bool) {
+        self.fit_width_axis = fit_width_axis;
+    }
+
+    pub fn set_features(&mut self, features: Vec<(SkFourByteTag, u32)>) {
+        self.features = features;
+    }
+
+    pub fn set_letter_spacing(&mut self, letter_spacing: LetterSpacing) {
+        self.letter_spacing = letter_spacing;
+    }
+
+    pub fn set_fit_precision(&mut self, fit_precision: SkScalar) {
+        self.fit_precision = fit_precision;
+    }
+
     pub fn set_text_align(&mut self, text_align: SkTextAlign) {
         self.text_align = text_align;
     }
@@ -83,6 +159,10 @@ impl Line {
         self.color = color;
     }
 
+    pub fn set_fill_shader(&mut self, fill_shader: Option<SkShader>) {
+        self.fill_shader = fill_shader;
+    }
+
     pub fn set_disable_stretch(&mut self, disable_stretch: bool) {
         self.disable_stretch = disable_stretch;
     }
@@ -99,6 +179,23 @@ impl Line {
         self.outline_color = outline_color;
     }
 
+    pub fn set_hinting(&mut self, hinting: SkFontHinting) {
+        self.hinting = hinting;
+    }
+
+    pub fn set_edging(&mut self, edging: SkFontEdging) {
+        self.edging = edging;
+    }
+
+    pub fn set_subpixel(&mut self, subpixel: bool) {
+        self.subpixel = subpixel;
+    }
+
+    // falseにするとアンチエイリアスを無効にし、グリフを整数のピクセルに揃えて描画する (ドット絵モード)
+    pub fn set_anti_alias(&mut self, anti_alias: bool) {
+        self.anti_alias = anti_alias;
+    }
+
     pub fn measure(&mut self, text_size: Option<SkScalar>) {
         if text_size.is_some() {
             self.measure_size_fixed(text_size.unwrap());
@@ -138,10 +235,26 @@ impl Line {
         // for Y-axis
         let offset_y = (self.line_height - self.spec.bounds.height()) / 2.0;
 
+        let mut origin = Point::new(x, y - self.spec.bounds.top + offset_y);
+        if !self.anti_alias {
+            origin = Point::new(origin.x.round(), origin.y.round());
+        }
+
+        // OpenTypeフィーチャー・字間指定時はグリフを個別に配置して描画
+        let mut run = self.glyph_run(&font);
+        if !self.anti_alias {
+            if let Some(run) = &mut run {
+                run.snap_to_pixels();
+            }
+        }
+
         // outline
         if !self.disable_outline {
             // draw outline
-            let text_path = SkTextUtils::get_path(&self.text, Point::new(x, y - self.spec.bounds.top + offset_y), &font);
+            let text_path = match &run {
+                Some(run) => run.path(&font, origin),
+                None => SkTextUtils::get_path(&self.text, origin, &font),
+            };
             paint.set_style(SkStyle::Stroke);
             paint.set_stroke_width(self.outline_width);
             paint.set_color(self.outline_color);
@@ -154,7 +267,13 @@ impl Line {
 
         // text
         paint.set_color(self.color);
-        SkTextUtils::draw_str(canvas, &self.text, Point::new(x, y - self.spec.bounds.top + offset_y), &font, &paint, SkTextAlign::Left);
+        if let Some(shader) = &self.fill_shader {
+            paint.set_shader(shader.clone());
+        }
+        match &run {
+            Some(run) => canvas.draw_glyphs_at(&run.glyphs, run.positions.as_slice(), origin, &font, &paint),
+            None => SkTextUtils::draw_str(canvas, &self.text, origin, &font, &paint, SkTextAlign::Left),
+        }
     }
 
     // MeasureSpec
@@ -163,113 +282,190 @@ impl Line {
     pub fn measure_adjusted(&mut self) {
         let paint = self.prepare_paint_for_measure();
         let mut font = self.prepare_font_for_measure();
-        let mut bounds;
 
-        let mut min_text_size = self.line_height * 0.9;
+        // 非伸縮モードでは幅にも収まるよう、下限を0まで下げる
+        let min_text_size = if self.disable_stretch { 0.0 } else { self.line_height * 0.9 };
         let max_text_size = self.line_height * 10.0;
 
-        let mut prev_bounds = SkRect::new_empty();
-        let mut prev_text_size = 0.0;
-
+        let mut text_size = 0.0;
         if !self.text.is_empty() {
-            // 非伸縮モード: 初期フォントサイズを調整
-            if self.disable_stretch {
-                let mut i = min_text_size;
-                loop {
-                    if i < 0.0 { break }
-                    
-                    font.set_size(i);
-                    bounds = font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, Some(&paint)).1;
-
-                    if bounds.width() < self.width {
-                        min_text_size = i;
-                        break;
-                    }
-
-                    i -= 0.5;
-                }
-            }
-
-            let mut i = min_text_size;
-            loop {
-                if i > max_text_size { break }
-
-                font.set_size(i);
-                bounds = font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, Some(&paint)).1;
-
-                prev_text_size = i;
-                prev_bounds = bounds;
-
-                if bounds.height() > self.line_height { break }
-                if self.disable_stretch && bounds.width() > self.width { break }
-
-                i += 0.5;
+            let fits = |line: &Line, font: &mut SkFont, size: SkScalar| {
+                font.set_size(size);
+                let bounds = line.measure_bounds(font, Some(&paint));
+                bounds.height() <= line.line_height && !(line.disable_stretch && bounds.width() > line.width)
+            };
+
+            // 下限でも収まらなければ下限、上限でも収まるなら上限、それ以外は二分探索
+            if !fits(self, &mut font, min_text_size) {
+                text_size = min_text_size;
+            } else if fits(self, &mut font, max_text_size) {
+                text_size = max_text_size;
+            } else {
+                text_size = bisect(min_text_size, max_text_size, self.fit_precision, |size| fits(self, &mut font, size));
             }
         }
 
+        font.set_size(text_size);
+        let bounds = self.measure_bounds(&font, Some(&paint));
+
         self.spec.text_scale_x = 1.0;
-        self.spec.text_size = prev_text_size;
-        self.spec.bounds = prev_bounds;
+        self.spec.text_size = text_size;
+        self.spec.bounds = bounds;
+        self.spec.typeface = None;
 
         // 横方向圧縮が必要な場合: 圧縮率の調整
-        if prev_bounds.width() > self.width {
-            font.set_size(prev_text_size);
-
-            let mut i = self.width / prev_bounds.width();
-            loop {
-                if i < 0.0 { break }
-
-                font.set_scale_x(i);
-                bounds = font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, Some(&paint)).1;
-
-                if bounds.width() <= self.width {
-                    self.spec.bounds = bounds;
-                    self.spec.text_scale_x = i;
-                    break;
-                }
-
-                i -= 0.0001;
-            }
+        if bounds.width() > self.width {
+            self.fit_scale_x(&mut font, Some(&paint));
         }
     }
 
     // テキストサイズ固定モード
     pub fn measure_size_fixed(&mut self, text_size: SkScalar) {
         let mut font = self.prepare_font_for_measure();
-        let mut bounds;
 
         font.set_size(text_size);
-        bounds = font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, None).1;
+        let bounds = self.measure_bounds(&font, None);
 
         self.spec.text_scale_x = 1.0;
         self.spec.text_size = text_size;
         self.spec.bounds = bounds;
+        self.spec.typeface = None;
 
         // 横方向圧縮が必要な場合: 圧縮率の調整
         if bounds.width() > self.width {
-            let mut i = self.width / bounds.width();
-            loop {
-                if i < 0.0 { break }
+            self.fit_scale_x(&mut font, None);
+        }
+    }
+
+    // 横方向の圧縮率を、幅に収まる最大の値に調整する
+    pub fn fit_scale_x(&mut self, font: &mut SkFont, paint: Option<&SkPaint>) {
+        // バリアブルフォント: 圧縮する前にwdth軸で幅を狭める
+        let narrowed = self.narrow_width_axis(font, paint);
+        if narrowed.width() <= self.width {
+            self.spec.bounds = narrowed;
+            return;
+        }
 
-                font.set_scale_x(i);
-                bounds = font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, None).1;
+        let full_width = narrowed.width();
+        let mut low = 0.0;
+        let mut high = 1.0;
 
-                if bounds.width() <= self.width {
-                    self.spec.bounds = bounds;
-                    self.spec.text_scale_x = i;
-                    break;
+        // 幅は圧縮率に対してほぼ線形なので、2点から収まる圧縮率を推定して探索範囲を狭める
+        let estimate = self.width / full_width;
+        font.set_scale_x(estimate);
+        let estimate_width = self.measure_bounds(font, paint).width();
+        if estimate_width <= self.width {
+            low = estimate;
+        } else {
+            high = estimate;
+
+            let slope = (full_width - estimate_width) / (1.0 - estimate);
+            if slope > 0.0 {
+                let guess = estimate - (estimate_width - self.width) / slope;
+                if guess > low && guess < high {
+                    font.set_scale_x(guess);
+                    if self.measure_bounds(font, paint).width() <= self.width {
+                        low = guess;
+                    } else {
+                        high = guess;
+                    }
                 }
+            }
+        }
 
-                i -= 0.0001;
+        // 下限が収まることを確かめる。縁取りは圧縮されないため、太い縁取りではどの圧縮率でも収まらないことがある
+        // その場合は0まで潰さず、推定した圧縮率のまま縁取りの分だけはみ出させる
+        if low == 0.0 {
+            font.set_scale_x(low);
+            if self.measure_bounds(font, paint).width() > self.width {
+                font.set_scale_x(estimate);
+                self.spec.bounds = self.measure_bounds(font, paint);
+                self.spec.text_scale_x = estimate;
+                return;
             }
         }
+
+        let width = self.width;
+        let scale_x = bisect(low, high, self.fit_precision / full_width, |scale_x| {
+            font.set_scale_x(scale_x);
+            self.measure_bounds(font, paint).width() <= width
+        });
+
+        font.set_scale_x(scale_x);
+        self.spec.bounds = self.measure_bounds(font, paint);
+        self.spec.text_scale_x = scale_x;
+    }
+
+    // wdth軸を二分探索で狭め、収まる最大の値を適用する (収まらない場合は最小値)
+    // 探索はfit_precision (px) を軸の値に換算した精度で打ち切る
+    pub fn narrow_width_axis(&mut self, font: &mut SkFont, paint: Option<&SkPaint>) -> SkRect {
+        let bounds = self.measure_bounds(font, paint);
+        if !self.fit_width_axis {
+            return bounds;
+        }
+
+        let axis = self.typeface.variation_design_parameters().unwrap_or_default().into_iter().find(|axis| axis.tag == WIDTH_AXIS);
+        if axis.is_none() {
+            return bounds;
+        }
+
+        let axis = axis.unwrap();
+        let position = self.typeface.variation_design_position().unwrap_or_default();
+        let current = position.iter().find(|coordinate| coordinate.axis == WIDTH_AXIS).map(|coordinate| coordinate.value).unwrap_or(axis.def);
+        if axis.min >= current {
+            return bounds;
+        }
+
+        let min_tf = self.clone_with_width_axis(&position, axis.min);
+        if min_tf.is_none() {
+            return bounds;
+        }
+
+        let min_tf = min_tf.unwrap();
+        font.set_typeface(&min_tf);
+        let min_measured = self.measure_bounds(font, paint);
+        if min_measured.width() > self.width {
+            // 最小値でも収まらない: 最小値のまま横方向圧縮に任せる
+            self.spec.typeface = Some(min_tf);
+            return min_measured;
+        }
+
+        // 幅は軸の値に対してほぼ線形なので、1pxあたりの軸の変化量から精度を求める
+        let span = bounds.width() - min_measured.width();
+        let precision = if span > 0.0 { self.fit_precision * (current - axis.min) / span } else { current - axis.min };
+
+        let width = self.width;
+        let value = bisect(axis.min, current, precision, |value| {
+            match self.clone_with_width_axis(&position, value) {
+                Some(tf) => {
+                    font.set_typeface(&tf);
+                    self.measure_bounds(font, paint).width() <= width
+                },
+                None => false,
+            }
+        });
+
+        let tf = if value > axis.min { self.clone_with_width_axis(&position, value).unwrap_or(min_tf) } else { min_tf };
+        font.set_typeface(&tf);
+        let measured = self.measure_bounds(font, paint);
+        self.spec.typeface = Some(tf);
+        return measured;
     }
 
+    pub fn clone_with_width_axis(&self, position: &[SkCoordinate], value: SkScalar) -> Option<SkTypeface> {
+        let mut coordinates: Vec<SkCoordinate> = position.iter().filter(|coordinate| coordinate.axis != WIDTH_AXIS).cloned().collect();
+        coordinates.push(SkCoordinate { axis: WIDTH_AXIS, value });
+
+        let args = SkFontArguments::new().set_variation_design_position(SkVariationPosition {
+            coordinates: &coordinates,
+        });
+        return self.typeface.clone_with_arguments(&args);
+    }
 
     // Utils
     pub fn prepare_paint_for_measure(&self) -> SkPaint {
         let mut paint = SkPaint::default();
-        paint.set_anti_alias(true);
+        paint.set_anti_alias(self.anti_alias);
         paint.set_color4f(SkColors::BLACK, None);
 
         if !self.disable_outline {
@@ -282,24 +478,69 @@ impl Line {
 
     pub fn prepare_paint_for_draw(&self) -> SkPaint {
         let mut paint = SkPaint::default();
-        paint.set_anti_alias(true);
+        paint.set_anti_alias(self.anti_alias);
         return paint;
     }
 
     pub fn prepare_font_for_measure(&self) -> SkFont {
         let mut font = SkFont::default();
         font.set_typeface(&self.typeface);
+        self.apply_fake_style(&mut font);
         return font;
     }
 
     pub fn prepare_font_for_draw(&self) -> SkFont {
         let mut font = SkFont::default();
-        font.set_typeface(&self.typeface);
+        font.set_typeface(self.spec.typeface.as_ref().unwrap_or(&self.typeface));
+        self.apply_fake_style(&mut font);
         font.set_size(self.spec.text_size);
         font.set_scale_x(self.spec.text_scale_x);
+        font.set_hinting(self.hinting);
+        font.set_edging(self.edging);
+        font.set_subpixel(self.subpixel);
+
+        // ドット絵モードではエッジ・ベースラインをピクセルに揃える
+        if !self.anti_alias {
+            font.set_edging(SkFontEdging::Alias);
+            font.set_subpixel(false);
+            font.set_baseline_snap(true);
+        }
         return font;
     }
 
+    pub fn measure_bounds(&self, font: &SkFont, paint: Option<&SkPaint>) -> SkRect {
+        match self.glyph_run(font) {
+            Some(run) => run.bounds(font, paint),
+            None => font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, paint).1,
+        }
+    }
+
+    pub fn glyph_run(&self, font: &SkFont) -> Option<shaping::GlyphRun> {
+        let letter_spacing = self.get_letter_spacing(font);
+        if self.features.is_empty() && letter_spacing == 0.0 {
+            return None;
+        }
+
+        return Some(shaping::layout(&self.text, font, &self.features, letter_spacing));
+    }
+
+    // 字間 (横方向圧縮時は字間も同じ比率で圧縮する)
+    pub fn get_letter_spacing(&self, font: &SkFont) -> SkScalar {
+        match self.letter_spacing {
+            LetterSpacing::Absolute(spacing) => spacing * font.scale_x(),
+            LetterSpacing::Em(spacing) => spacing * font.size() * font.scale_x(),
+        }
+    }
+
+    pub fn apply_fake_style(&self, font: &mut SkFont) {
+        font.set_embolden(self.fake_bold);
+
+        // 疑似イタリック: Skiaの標準的な傾き量
+        if self.fake_italic {
+            font.set_skew_x(-0.25);
+        }
+    }
+
     pub fn get_text_size(&self) -> SkScalar {
         return self.spec.text_size;
     }
@@ -308,8 +549,26 @@ impl Line {
         let paint = self.prepare_paint_for_measure();
         let mut font = self.prepare_font_for_measure();
         font.set_size(self.spec.text_size);
-        let bounds = font.measure_text(self.text.as_bytes(), SkTextEncoding::UTF8, Some(&paint)).1;
+        let bounds = self.measure_bounds(&font, Some(&paint));
 
         return bounds.width();
     }
-}
\ No newline at end of file
+}
+
+// lowは条件を満たし、highは満たさないものとして、条件を満たす最大値をprecisionの精度で探索する
+fn bisect(mut low: SkScalar, mut high: SkScalar, precision: SkScalar, mut fits: impl FnMut(SkScalar) -> bool) -> SkScalar {
+    while high - low > precision {
+        let mid = (low + high) / 2.0;
+        // 浮動小数点の精度の限界に達したら打ち切る
+        if mid <= low || mid >= high {
+            break;
+        }
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    return low;
+}
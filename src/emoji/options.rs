@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+
+// 絵文字の生成オプション (未指定の項目はGeneratorの既定値を使用する)
+// serdeでは綴りの誤りに気付けるよう、未知の項目はエラーにする
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Options {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub flexible_width: Option<bool>,
+    pub color: Option<String>,
+    pub color_palette: Option<Vec<String>>,
+    pub fill_image: Option<Vec<u8>>,
+    pub fill_image_tile_mode: Option<String>,
+    pub fill_image_scale: Option<f32>,
+    pub background_color: Option<String>,
+    pub text_align: Option<String>,
+    pub text_size_fixed: Option<bool>,
+    pub disable_stretch: Option<bool>,
+    pub disable_outline: Option<bool>,
+    pub outline_width: Option<u32>,
+    pub outline_color: Option<String>,
+    pub shape: Option<String>,
+    pub shape_color: Option<String>,
+    pub shape_border_width: Option<u32>,
+    pub shape_border_color: Option<String>,
+    pub shape_radius: Option<u32>,
+    pub shape_padding: Option<u32>,
+    pub background_image: Option<Vec<u8>>,
+    pub background_image_fit: Option<String>,
+    pub overlay_images: Option<Vec<OverlayImage>>,
+    pub trim: Option<String>,
+    pub trim_padding: Option<u32>,
+    pub typeface_file: Option<String>,
+    pub typeface_index: Option<u32>,
+    pub typeface_name: Option<String>,
+    pub typeface_weight: Option<u32>,
+    pub typeface_width: Option<String>,
+    pub typeface_slant: Option<String>,
+    pub typeface_variations: Option<HashMap<String, f32>>,
+    pub fit_width_axis: Option<bool>,
+    pub opentype_features: Option<HashMap<String, u32>>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "string_or_number"))]
+    pub letter_spacing: Option<String>,
+    pub fit_precision: Option<f32>,
+    pub font_hinting: Option<String>,
+    pub font_edging: Option<String>,
+    pub font_subpixel: Option<bool>,
+    pub supersample: Option<u32>,
+    pub pixel_art: Option<bool>,
+    pub palette_size: Option<u32>,
+    pub max_bytes: Option<u32>,
+    pub format: Option<String>,
+    pub matte_color: Option<String>,
+    pub alpha_loss: Option<String>,
+    pub quality: Option<u32>,
+}
+
+// 絵文字の上に重ねる画像 (エンコード済みの画像と、配置する位置・大きさ)
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct OverlayImage {
+    pub image: Vec<u8>,
+    pub x: i32,
+    pub y: i32,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+// 数値でも指定できる文字列の項目 (例: letter_spacingの 2 と "2px")
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrNumber {
+    String(String),
+    Number(f64),
+}
+
+#[cfg(feature = "serde")]
+fn string_or_number<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<StringOrNumber>::deserialize(deserializer)?;
+    return Ok(value.map(|value| match value {
+        StringOrNumber::String(value) => value,
+        StringOrNumber::Number(value) => value.to_string(),
+    }));
+}
+
+impl Options {
+    // overridesで指定された項目を優先して2つのオプションを合成する
+    pub fn merge(self, overrides: Options) -> Options {
+        Options {
+            width: overrides.width.or(self.width),
+            height: overrides.height.or(self.height),
+            flexible_width: overrides.flexible_width.or(self.flexible_width),
+            color: overrides.color.or(self.color),
+            color_palette: overrides.color_palette.or(self.color_palette),
+            fill_image: overrides.fill_image.or(self.fill_image),
+            fill_image_tile_mode: overrides.fill_image_tile_mode.or(self.fill_image_tile_mode),
+            fill_image_scale: overrides.fill_image_scale.or(self.fill_image_scale),
+            background_color: overrides.background_color.or(self.background_color),
+            text_align: overrides.text_align.or(self.text_align),
+            text_size_fixed: overrides.text_size_fixed.or(self.text_size_fixed),
+            disable_stretch: overrides.disable_stretch.or(self.disable_stretch),
+            disable_outline: overrides.disable_outline.or(self.disable_outline),
+            outline_width: overrides.outline_width.or(self.outline_width),
+            outline_color: overrides.outline_color.or(self.outline_color),
+            shape: overrides.shape.or(self.shape),
+            shape_color: overrides.shape_color.or(self.shape_color),
+            shape_border_width: overrides.shape_border_width.or(self.shape_border_width),
+            shape_border_color: overrides.shape_border_color.or(self.shape_border_color),
+            shape_radius: overrides.shape_radius.or(self.shape_radius),
+            shape_padding: overrides.shape_padding.or(self.shape_padding),
+            background_image: overrides.background_image.or(self.background_image),
+            background_image_fit: overrides.background_image_fit.or(self.background_image_fit),
+            overlay_images: overrides.overlay_images.or(self.overlay_images),
+            trim: overrides.trim.or(self.trim),
+            trim_padding: overrides.trim_padding.or(self.trim_padding),
+            typeface_file: overrides.typeface_file.or(self.typeface_file),
+            typeface_index: overrides.typeface_index.or(self.typeface_index),
+            typeface_name: overrides.typeface_name.or(self.typeface_name),
+            typeface_weight: overrides.typeface_weight.or(self.typeface_weight),
+            typeface_width: overrides.typeface_width.or(self.typeface_width),
+            typeface_slant: overrides.typeface_slant.or(self.typeface_slant),
+            typeface_variations: overrides.typeface_variations.or(self.typeface_variations),
+            fit_width_axis: overrides.fit_width_axis.or(self.fit_width_axis),
+            opentype_features: overrides.opentype_features.or(self.opentype_features),
+            letter_spacing: overrides.letter_spacing.or(self.letter_spacing),
+            fit_precision: overrides.fit_precision.or(self.fit_precision),
+            font_hinting: overrides.font_hinting.or(self.font_hinting),
+            font_edging: overrides.font_edging.or(self.font_edging),
+            font_subpixel: overrides.font_subpixel.or(self.font_subpixel),
+            supersample: overrides.supersample.or(self.supersample),
+            pixel_art: overrides.pixel_art.or(self.pixel_art),
+            palette_size: overrides.palette_size.or(self.palette_size),
+            max_bytes: overrides.max_bytes.or(self.max_bytes),
+            format: overrides.format.or(self.format),
+            matte_color: overrides.matte_color.or(self.matte_color),
+            alpha_loss: overrides.alpha_loss.or(self.alpha_loss),
+            quality: overrides.quality.or(self.quality),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use skia_safe::Point;
+use skia_safe::scalar as SkScalar;
+use skia_safe::Rect as SkRect;
+use skia_safe::RRect as SkRRect;
+use skia_safe::Path as SkPath;
+use skia_safe::PathOp as SkPathOp;
+use skia_safe::Color as SkColor;
+use skia_safe::Canvas as SkCanvas;
+use skia_safe::paint::Paint as SkPaint;
+use skia_safe::paint::Style as SkStyle;
+
+// 角丸の内側に収まる矩形を求めるときの、角からの食い込み量の比率 (1 - 1/√2)
+const CORNER_INSET: SkScalar = 1.0 - std::f32::consts::FRAC_1_SQRT_2;
+// スーパー楕円 (|x|^4 + |y|^4 = 1) に内接する正方形の比率 (2^(-1/4))
+const SQUIRCLE_SCALE: SkScalar = 0.840_896_4;
+// 吹き出しの高さに占めるしっぽの比率
+const BUBBLE_TAIL_RATIO: SkScalar = 0.15;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShapeKind {
+    Rect,
+    Circle,
+    RoundedRect,
+    Pill,
+    Squircle,
+    SpeechBubble,
+}
+
+pub struct Shape {
+    kind: ShapeKind,
+    color: SkColor,
+    border_width: SkScalar,
+    border_color: SkColor,
+    radius: Option<SkScalar>,
+    padding: SkScalar,
+    anti_alias: bool,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape {
+    pub fn new() -> Self {
+        Self {
+            kind: ShapeKind::Rect,
+            color: SkColor::WHITE,
+            border_width: 0.0,
+            border_color: SkColor::BLACK,
+            radius: None,
+            padding: 0.0,
+            anti_alias: true,
+        }
+    }
+
+    pub fn set_kind(&mut self, kind: ShapeKind) {
+        self.kind = kind;
+    }
+
+    pub fn set_color(&mut self, color: SkColor) {
+        self.color = color;
+    }
+
+    pub fn get_color(&self) -> SkColor {
+        return self.color;
+    }
+
+    pub fn set_border_width(&mut self, border_width: SkScalar) {
+        self.border_width = border_width;
+    }
+
+    pub fn set_border_color(&mut self, border_color: SkColor) {
+        self.border_color = border_color;
+    }
+
+    pub fn set_radius(&mut self, radius: SkScalar) {
+        self.radius = Some(radius);
+    }
+
+    pub fn set_padding(&mut self, padding: SkScalar) {
+        self.padding = padding;
+    }
+
+    pub fn set_anti_alias(&mut self, anti_alias: bool) {
+        self.anti_alias = anti_alias;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        return self.kind != ShapeKind::Rect || self.border_width > 0.0 || self.padding > 0.0;
+    }
+
+    // 図形の内側をshape_colorで塗るか (矩形は背景色で塗るため塗らない)
+    pub fn is_filled(&self) -> bool {
+        return self.kind != ShapeKind::Rect;
+    }
+
+    // 図形の外形 (枠線の中心線) の矩形。吹き出しの場合はしっぽを除いた本体部分
+    fn body_rect(&self, width: SkScalar, height: SkScalar) -> SkRect {
+        let half = self.border_width / 2.0;
+        let mut rect = SkRect::new(half, half, width - half, height - half);
+        if self.kind == ShapeKind::SpeechBubble {
+            rect.bottom -= rect.height() * BUBBLE_TAIL_RATIO;
+        }
+
+        return rect;
+    }
+
+    fn corner_radius(&self, rect: &SkRect) -> SkScalar {
+        let max_radius = rect.width().min(rect.height()) / 2.0;
+        match self.kind {
+            ShapeKind::Pill => max_radius,
+            ShapeKind::RoundedRect | ShapeKind::SpeechBubble => self.radius.unwrap_or(max_radius * 0.4).clamp(0.0, max_radius),
+            _ => 0.0,
+        }
+    }
+
+    pub fn path(&self, width: SkScalar, height: SkScalar) -> SkPath {
+        let rect = self.body_rect(width, height);
+        let mut path = SkPath::new();
+
+        match self.kind {
+            ShapeKind::Rect => {
+                path.add_rect(rect, None);
+            },
+            ShapeKind::Circle => {
+                path.add_oval(rect, None);
+            },
+            ShapeKind::RoundedRect | ShapeKind::Pill => {
+                let radius = self.corner_radius(&rect);
+                path.add_rrect(SkRRect::new_rect_xy(rect, radius, radius), None);
+            },
+            ShapeKind::Squircle => {
+                // スーパー楕円を多角形で近似する
+                let (cx, cy) = (rect.center_x(), rect.center_y());
+                let (rx, ry) = (rect.width() / 2.0, rect.height() / 2.0);
+                let points: Vec<Point> = (0..128).map(|i| {
+                    let t = i as SkScalar / 128.0 * std::f32::consts::TAU;
+                    let x = t.cos().signum() * t.cos().abs().sqrt();
+                    let y = t.sin().signum() * t.sin().abs().sqrt();
+                    Point::new(cx + rx * x, cy + ry * y)
+                }).collect();
+                path.add_poly(&points, true);
+            },
+            ShapeKind::SpeechBubble => {
+                let radius = self.corner_radius(&rect);
+                path.add_rrect(SkRRect::new_rect_xy(rect, radius, radius), None);
+
+                // 左下にしっぽを付け、枠線が継ぎ目に出ないよう本体と合成する
+                let bottom = height - self.border_width / 2.0;
+                let mut tail = SkPath::new();
+                tail.add_poly(&[
+                    Point::new(rect.left + rect.width() * 0.25, rect.bottom - 1.0),
+                    Point::new(rect.left + rect.width() * 0.45, rect.bottom - 1.0),
+                    Point::new(rect.left + rect.width() * 0.15, bottom),
+                ], true);
+                if let Some(union) = path.op(&tail, SkPathOp::Union) {
+                    path = union;
+                }
+            },
+        }
+
+        return path;
+    }
+
+    // テキストを配置する領域 (図形に内接する矩形から枠線と余白を除いたもの)
+    pub fn content_rect(&self, width: SkScalar, height: SkScalar) -> SkRect {
+        let rect = self.body_rect(width, height);
+        let inner = rect.with_inset((self.border_width / 2.0, self.border_width / 2.0));
+
+        let content = match self.kind {
+            ShapeKind::Circle => scale_rect(&inner, std::f32::consts::FRAC_1_SQRT_2),
+            ShapeKind::Squircle => scale_rect(&inner, SQUIRCLE_SCALE),
+            _ => {
+                let inset = self.corner_radius(&rect) * CORNER_INSET;
+                inner.with_inset((inset, inset))
+            },
+        };
+
+        let content = content.with_inset((self.padding, self.padding));
+        return SkRect::new(content.left, content.top, content.right.max(content.left), content.bottom.max(content.top));
+    }
+
+    // テキストの幅から図形を含めた画像の幅を求める (幅可変モード用)
+    pub fn outer_width(&self, content_width: SkScalar, height: SkScalar) -> SkScalar {
+        let width = content_width + self.padding * 2.0;
+        match self.kind {
+            ShapeKind::Circle => width / std::f32::consts::FRAC_1_SQRT_2 + self.border_width * 2.0,
+            ShapeKind::Squircle => width / SQUIRCLE_SCALE + self.border_width * 2.0,
+            _ => {
+                // 横長を前提として、角丸の大きさは高さから決まる
+                let rect = self.body_rect(SkScalar::MAX / 2.0, height);
+                width + (self.corner_radius(&rect) * CORNER_INSET + self.border_width) * 2.0
+            },
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut SkCanvas, width: SkScalar, height: SkScalar) {
+        let path = self.path(width, height);
+
+        let mut paint = SkPaint::default();
+        paint.set_anti_alias(self.anti_alias);
+
+        // 矩形の塗りつぶしは背景色で行うため、枠線のみ描画する
+        if self.is_filled() {
+            paint.set_style(SkStyle::Fill);
+            paint.set_color(self.color);
+            canvas.draw_path(&path, &paint);
+        }
+
+        if self.border_width > 0.0 {
+            paint.set_style(SkStyle::Stroke);
+            paint.set_stroke_width(self.border_width);
+            paint.set_color(self.border_color);
+            canvas.draw_path(&path, &paint);
+        }
+    }
+}
+
+// 中心を保ったまま大きさをscale倍した矩形
+fn scale_rect(rect: &SkRect, scale: SkScalar) -> SkRect {
+    let (half_width, half_height) = (rect.width() * scale / 2.0, rect.height() * scale / 2.0);
+    return SkRect::new(rect.center_x() - half_width, rect.center_y() - half_height, rect.center_x() + half_width, rect.center_y() + half_height);
+}
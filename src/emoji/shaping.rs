@@ -0,0 +1,147 @@
+use skia_safe::Point;
+use skia_safe::scalar as SkScalar;
+use skia_safe::Rect as SkRect;
+use skia_safe::Path as SkPath;
+use skia_safe::Font as SkFont;
+use skia_safe::GlyphId as SkGlyphId;
+use skia_safe::FourByteTag as SkFourByteTag;
+use skia_safe::paint::Paint as SkPaint;
+use skia_safe::icu as SkIcu;
+use skia_safe::Shaper as SkShaper;
+use skia_safe::shaper::Feature as SkFeature;
+use skia_safe::shaper::RunHandler as SkRunHandler;
+use skia_safe::shaper::run_handler::Buffer as SkBuffer;
+use skia_safe::shaper::run_handler::RunInfo as SkRunInfo;
+
+// シェーピング結果のグリフ列 (位置は原点からの相対座標、クラスタはグリフに対応するテキストのバイト位置)
+pub struct GlyphRun {
+    pub glyphs: Vec<SkGlyphId>,
+    pub positions: Vec<Point>,
+    pub clusters: Vec<u32>,
+}
+
+impl GlyphRun {
+    pub fn bounds(&self, font: &SkFont, paint: Option<&SkPaint>) -> SkRect {
+        let mut glyph_bounds = vec![SkRect::new_empty(); self.glyphs.len()];
+        font.get_bounds(&self.glyphs, &mut glyph_bounds, paint);
+
+        let mut bounds = SkRect::new_empty();
+        for (rect, position) in glyph_bounds.iter().zip(self.positions.iter()) {
+            if rect.is_empty() {
+                continue;
+            }
+            bounds.join(rect.with_offset(*position));
+        }
+
+        return bounds;
+    }
+
+    // 各グリフの位置を整数のピクセルに揃える (ドット絵モード用)
+    pub fn snap_to_pixels(&mut self) {
+        for position in &mut self.positions {
+            *position = Point::new(position.x.round(), position.y.round());
+        }
+    }
+
+    pub fn path(&self, font: &SkFont, origin: Point) -> SkPath {
+        let mut path = SkPath::new();
+        for (glyph, position) in self.glyphs.iter().zip(self.positions.iter()) {
+            if let Some(glyph_path) = font.get_path(*glyph) {
+                path.add_path(&glyph_path, origin + *position, None);
+            }
+        }
+
+        return path;
+    }
+}
+
+struct GlyphCollector {
+    glyphs: Vec<SkGlyphId>,
+    positions: Vec<Point>,
+    clusters: Vec<u32>,
+    advance: SkScalar,
+}
+
+impl SkRunHandler for GlyphCollector {
+    fn begin_line(&mut self) {}
+
+    fn run_info(&mut self, _info: &SkRunInfo) {}
+
+    fn commit_run_info(&mut self) {}
+
+    fn run_buffer(&mut self, info: &SkRunInfo) -> SkBuffer {
+        let start = self.glyphs.len();
+        self.glyphs.resize(start + info.glyph_count, 0);
+        self.positions.resize(start + info.glyph_count, Point::default());
+        self.clusters.resize(start + info.glyph_count, 0);
+
+        let mut buffer = SkBuffer::new(&mut self.glyphs[start..], &mut self.positions[start..], Point::new(self.advance, 0.0));
+        buffer.clusters = Some(&mut self.clusters[start..]);
+        return buffer;
+    }
+
+    fn commit_run_buffer(&mut self, info: &SkRunInfo) {
+        self.advance += info.advance.x;
+    }
+
+    fn commit_line(&mut self) {}
+}
+
+// グリフ列を配置する (シェーピングし、字間はクラスタの間にのみ加算する)
+// 結合文字・合字・複数グリフからなるクラスタは離さないよう、同じクラスタのグリフは同じだけずらす
+pub fn layout(text: &str, font: &SkFont, features: &[(SkFourByteTag, u32)], letter_spacing: SkScalar) -> GlyphRun {
+    let mut run = shape(text, font, features);
+
+    let mut index = 0;
+    for i in 0..run.positions.len() {
+        if i > 0 && run.clusters[i] != run.clusters[i - 1] {
+            index += 1;
+        }
+        run.positions[i].x += letter_spacing * index as SkScalar;
+    }
+
+    return run;
+}
+
+// OpenTypeフィーチャーを適用してテキストをシェーピングする
+pub fn shape(text: &str, font: &SkFont, features: &[(SkFourByteTag, u32)]) -> GlyphRun {
+    let mut collector = GlyphCollector {
+        glyphs: Vec::new(),
+        positions: Vec::new(),
+        clusters: Vec::new(),
+        advance: 0.0,
+    };
+
+    if text.is_empty() {
+        return GlyphRun { glyphs: collector.glyphs, positions: collector.positions, clusters: collector.clusters };
+    }
+
+    SkIcu::init();
+
+    let features: Vec<SkFeature> = features.iter().map(|(tag, value)| SkFeature {
+        tag: **tag,
+        value: *value,
+        start: 0,
+        end: usize::MAX,
+    }).collect();
+
+    let bytes = text.len();
+    let mut font_runs = SkShaper::new_trivial_font_run_iterator(font, bytes);
+    let mut bidi_runs = SkShaper::new_trivial_bidi_run_iterator(0, bytes);
+    let mut script_runs = SkShaper::new_hb_icu_script_run_iterator(text);
+    let mut language_runs = SkShaper::new_std_language_run_iterator(text).unwrap_or_else(|| SkShaper::new_trivial_language_run_iterator("ja"));
+
+    let shaper = SkShaper::new(None);
+    shaper.shape_with_iterators_and_features(
+        text,
+        &mut font_runs,
+        &mut bidi_runs,
+        &mut script_runs,
+        &mut language_runs,
+        &features,
+        SkScalar::MAX,
+        &mut collector,
+    );
+
+    return GlyphRun { glyphs: collector.glyphs, positions: collector.positions, clusters: collector.clusters };
+}
@@ -1,50 +1,171 @@
+use super::color;
+use super::error::Error;
+use super::image::{AlphaLoss, ImageFit, Trim};
+use super::line::LetterSpacing;
+use super::shape::ShapeKind;
 use skia_safe::Color as SkColor;
+use skia_safe::FontHinting as SkFontHinting;
+use skia_safe::font::Edging as SkFontEdging;
 use skia_safe::utils::text_utils::Align as SkTextAlign;
 use skia_safe::EncodedImageFormat as SkEncodedImageFormat;
+use skia_safe::font_style::Width as SkFontWidth;
+use skia_safe::font_style::Slant as SkFontSlant;
+use skia_safe::FourByteTag as SkFourByteTag;
+use skia_safe::TileMode as SkTileMode;
 
-pub fn parse_color_code(f_color: String) -> Result<SkColor, String> {
-    let mut color = f_color.to_string();
-    
-    if color.starts_with("#") {
-        color = color[1..].to_string();
+// CSS Color Level 4の構文 (#RGB, red, rgb(), hsl(), hwb(), transparentなど) で色を指定できる
+pub fn parse_color_code(f_color: String) -> Result<SkColor, Error> {
+    let color = color::parse_css_color(&f_color);
+    if color.is_none() {
+        return Err(Error::InvalidColor(format!("Invalid color code: {}", f_color)));
     }
 
-    // 色コードの文字列が16進数でなければエラー
-    if !color.chars().all(|c| c.is_digit(16)) {
-        return Err(format!("Invalid color code: {}", f_color));
+    return Ok(color.unwrap());
+}
+
+pub fn parse_text_align(text_align: String) -> Result<SkTextAlign, Error> {
+    match text_align.as_str() {
+        "left" => Ok(SkTextAlign::Left),
+        "center" => Ok(SkTextAlign::Center),
+        "right" => Ok(SkTextAlign::Right),
+        _ => Err(Error::InvalidTextAlign(format!("Invalid text align: {}", text_align))),
     }
+}
 
-    // 色コードの桁数が6桁でも8桁でもなければエラー
-    if color.len() != 6 && color.len() != 8 {
-        return Err(format!("color code must be 6 or 8 digits (eg. #000000, #000000FF)"));
+pub fn parse_shape(shape: String) -> Result<ShapeKind, Error> {
+    match shape.as_str() {
+        "rect" => Ok(ShapeKind::Rect),
+        "circle" => Ok(ShapeKind::Circle),
+        "rounded_rect" => Ok(ShapeKind::RoundedRect),
+        "pill" => Ok(ShapeKind::Pill),
+        "squircle" => Ok(ShapeKind::Squircle),
+        "speech_bubble" => Ok(ShapeKind::SpeechBubble),
+        _ => Err(Error::InvalidOption(format!("Invalid shape: {}", shape))),
     }
+}
 
-    // もし色コードが6桁なら8桁にする
-    if color.len() == 6 {
-        color.push_str("FF");
+pub fn parse_image_fit(fit: String) -> Result<ImageFit, Error> {
+    match fit.as_str() {
+        "cover" => Ok(ImageFit::Cover),
+        "contain" => Ok(ImageFit::Contain),
+        "stretch" => Ok(ImageFit::Stretch),
+        "tile" => Ok(ImageFit::Tile),
+        _ => Err(Error::InvalidOption(format!("Invalid image fit: {}", fit))),
     }
+}
 
-    let r = u8::from_str_radix(&color[0..2], 16).unwrap();
-    let g = u8::from_str_radix(&color[2..4], 16).unwrap();
-    let b = u8::from_str_radix(&color[4..6], 16).unwrap();
-    let a = u8::from_str_radix(&color[6..8], 16).unwrap();
+pub fn parse_tile_mode(tile_mode: String) -> Result<SkTileMode, Error> {
+    match tile_mode.as_str() {
+        "repeat" => Ok(SkTileMode::Repeat),
+        "mirror" => Ok(SkTileMode::Mirror),
+        "clamp" => Ok(SkTileMode::Clamp),
+        "decal" => Ok(SkTileMode::Decal),
+        _ => Err(Error::InvalidOption(format!("Invalid tile mode: {}", tile_mode))),
+    }
+}
 
-    return Ok(SkColor::from_argb(a, r, g, b));
+pub fn parse_font_hinting(hinting: String) -> Result<SkFontHinting, Error> {
+    match hinting.as_str() {
+        "none" => Ok(SkFontHinting::None),
+        "slight" => Ok(SkFontHinting::Slight),
+        "normal" => Ok(SkFontHinting::Normal),
+        "full" => Ok(SkFontHinting::Full),
+        _ => Err(Error::InvalidOption(format!("Invalid font hinting: {}", hinting))),
+    }
 }
 
-pub fn parse_text_align(text_align: String) -> Result<SkTextAlign, String> {
-    match text_align.as_str() {
-        "left" => Ok(SkTextAlign::Left),
-        "center" => Ok(SkTextAlign::Center),
-        "right" => Ok(SkTextAlign::Right),
-        _ => Err(format!("Invalid text align: {}", text_align)),
+pub fn parse_font_edging(edging: String) -> Result<SkFontEdging, Error> {
+    match edging.as_str() {
+        "alias" => Ok(SkFontEdging::Alias),
+        "antialias" => Ok(SkFontEdging::AntiAlias),
+        "subpixel_antialias" => Ok(SkFontEdging::SubpixelAntiAlias),
+        _ => Err(Error::InvalidOption(format!("Invalid font edging: {}", edging))),
+    }
+}
+
+pub fn parse_trim(trim: String) -> Result<Trim, Error> {
+    match trim.as_str() {
+        "none" => Ok(Trim::None),
+        "bounds" => Ok(Trim::Bounds),
+        "keep_height" => Ok(Trim::KeepHeight),
+        "keep_aspect" => Ok(Trim::KeepAspect),
+        _ => Err(Error::InvalidOption(format!("Invalid trim: {}", trim))),
+    }
+}
+
+pub fn parse_alpha_loss(alpha_loss: String) -> Result<AlphaLoss, Error> {
+    match alpha_loss.as_str() {
+        "ignore" => Ok(AlphaLoss::Ignore),
+        "warn" => Ok(AlphaLoss::Warn),
+        "error" => Ok(AlphaLoss::Error),
+        _ => Err(Error::InvalidOption(format!("Invalid alpha loss: {}", alpha_loss))),
     }
 }
 
-pub fn parse_image_format(format: String) -> Result<SkEncodedImageFormat, String> {
+pub fn parse_image_format(format: String) -> Result<SkEncodedImageFormat, Error> {
     match format.as_str() {
         "png" => Ok(SkEncodedImageFormat::PNG),
         "jpeg" => Ok(SkEncodedImageFormat::JPEG),
-        _ => Err(format!("Invalid image format: {}", format)),
+        _ => Err(Error::InvalidFormat(format!("Invalid image format: {}", format))),
+    }
+}
+
+pub fn image_format_name(format: SkEncodedImageFormat) -> &'static str {
+    match format {
+        SkEncodedImageFormat::JPEG => "jpeg",
+        _ => "png",
     }
-}
\ No newline at end of file
+}
+
+pub fn parse_font_width(width: String) -> Result<SkFontWidth, Error> {
+    match width.as_str() {
+        "ultra-condensed" => Ok(SkFontWidth::ULTRA_CONDENSED),
+        "extra-condensed" => Ok(SkFontWidth::EXTRA_CONDENSED),
+        "condensed" => Ok(SkFontWidth::CONDENSED),
+        "semi-condensed" => Ok(SkFontWidth::SEMI_CONDENSED),
+        "normal" => Ok(SkFontWidth::NORMAL),
+        "semi-expanded" => Ok(SkFontWidth::SEMI_EXPANDED),
+        "expanded" => Ok(SkFontWidth::EXPANDED),
+        "extra-expanded" => Ok(SkFontWidth::EXTRA_EXPANDED),
+        "ultra-expanded" => Ok(SkFontWidth::ULTRA_EXPANDED),
+        _ => Err(Error::InvalidOption(format!("Invalid font width: {}", width))),
+    }
+}
+
+pub fn parse_font_slant(slant: String) -> Result<SkFontSlant, Error> {
+    match slant.as_str() {
+        "upright" => Ok(SkFontSlant::Upright),
+        "italic" => Ok(SkFontSlant::Italic),
+        "oblique" => Ok(SkFontSlant::Oblique),
+        _ => Err(Error::InvalidOption(format!("Invalid font slant: {}", slant))),
+    }
+}
+
+pub fn parse_font_tag(tag: String) -> Result<SkFourByteTag, Error> {
+    // OpenTypeのタグは4文字のASCII文字列
+    if tag.len() != 4 || !tag.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        return Err(Error::InvalidOption(format!("Invalid font tag: {}", tag)));
+    }
+
+    let c: Vec<char> = tag.chars().collect();
+    return Ok(SkFourByteTag::from_chars(c[0], c[1], c[2], c[3]));
+}
+
+pub fn parse_letter_spacing(letter_spacing: String) -> Result<LetterSpacing, Error> {
+    let value = letter_spacing.trim();
+
+    // 単位無しまたはpxは絶対値、emはフォントサイズに対する相対値 (NaNや無限大は配置の計算を壊すため受け付けない)
+    if let Some(em) = value.strip_suffix("em") {
+        let result = em.trim().parse::<f32>();
+        if result.is_err() || !result.as_ref().unwrap().is_finite() {
+            return Err(Error::InvalidOption(format!("Invalid letter spacing: {}", letter_spacing)));
+        }
+        return Ok(LetterSpacing::Em(result.unwrap()));
+    }
+
+    let result = value.strip_suffix("px").unwrap_or(value).trim().parse::<f32>();
+    if result.is_err() || !result.as_ref().unwrap().is_finite() {
+        return Err(Error::InvalidOption(format!("Invalid letter spacing: {}", letter_spacing)));
+    }
+    return Ok(LetterSpacing::Absolute(result.unwrap()));
+}
@@ -0,0 +1,296 @@
+use crate::emoji;
+use crate::emoji::Error as EmojiError;
+use crate::emoji::Options;
+use napi::{bindgen_prelude::{Buffer, Either}, Error};
+use napi_derive::napi;
+use std::collections::HashMap;
+
+#[napi(object)]
+pub struct EmojiOptions {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub flexible_width: Option<bool>,
+    pub color: Option<String>,
+    pub color_palette: Option<Vec<String>>,
+    pub fill_image: Option<Buffer>,
+    #[napi(ts_type = "'repeat' | 'mirror' | 'clamp' | 'decal'")]
+    pub fill_image_tile_mode: Option<String>,
+    pub fill_image_scale: Option<f64>,
+    pub background_color: Option<String>,
+    #[napi(ts_type = "'left' | 'center' | 'right'")]
+    pub text_align: Option<String>,
+    pub text_size_fixed: Option<bool>,
+    pub disable_stretch: Option<bool>,
+    pub disable_outline: Option<bool>,
+    pub outline_width: Option<u32>,
+    pub outline_color: Option<String>,
+    #[napi(ts_type = "'rect' | 'circle' | 'rounded_rect' | 'pill' | 'squircle' | 'speech_bubble'")]
+    pub shape: Option<String>,
+    pub shape_color: Option<String>,
+    pub shape_border_width: Option<u32>,
+    pub shape_border_color: Option<String>,
+    pub shape_radius: Option<u32>,
+    pub shape_padding: Option<u32>,
+    pub background_image: Option<Buffer>,
+    #[napi(ts_type = "'cover' | 'contain' | 'stretch' | 'tile'")]
+    pub background_image_fit: Option<String>,
+    pub overlay_images: Option<Vec<OverlayImage>>,
+    #[napi(ts_type = "'none' | 'bounds' | 'keep_height' | 'keep_aspect'")]
+    pub trim: Option<String>,
+    pub trim_padding: Option<u32>,
+    pub typeface_file: Option<String>,
+    pub typeface_index: Option<u32>,
+    pub typeface_name: Option<String>,
+    pub typeface_weight: Option<u32>,
+    #[napi(ts_type = "'ultra-condensed' | 'extra-condensed' | 'condensed' | 'semi-condensed' | 'normal' | 'semi-expanded' | 'expanded' | 'extra-expanded' | 'ultra-expanded'")]
+    pub typeface_width: Option<String>,
+    #[napi(ts_type = "'upright' | 'italic' | 'oblique'")]
+    pub typeface_slant: Option<String>,
+    pub typeface_variations: Option<HashMap<String, f64>>,
+    pub fit_width_axis: Option<bool>,
+    pub opentype_features: Option<HashMap<String, u32>>,
+    #[napi(ts_type = "number | string")]
+    pub letter_spacing: Option<Either<f64, String>>,
+    pub fit_precision: Option<f64>,
+    #[napi(ts_type = "'none' | 'slight' | 'normal' | 'full'")]
+    pub font_hinting: Option<String>,
+    #[napi(ts_type = "'alias' | 'antialias' | 'subpixel_antialias'")]
+    pub font_edging: Option<String>,
+    pub font_subpixel: Option<bool>,
+    pub supersample: Option<u32>,
+    pub pixel_art: Option<bool>,
+    pub palette_size: Option<u32>,
+    pub max_bytes: Option<u32>,
+    #[napi(ts_type = "'png' | 'jpeg'")]
+    pub format: Option<String>,
+    pub matte_color: Option<String>,
+    #[napi(ts_type = "'ignore' | 'warn' | 'error'")]
+    pub alpha_loss: Option<String>,
+    pub quality: Option<u32>,
+}
+
+#[napi(object)]
+pub struct OverlayImage {
+    pub image: Buffer,
+    pub x: i32,
+    pub y: i32,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[napi(object)]
+pub struct TypefaceFace {
+    pub index: u32,
+    pub family: String,
+    pub weight: i32,
+    pub width: i32,
+    #[napi(ts_type = "'upright' | 'italic' | 'oblique'")]
+    pub slant: String,
+}
+
+#[napi(object)]
+pub struct TypefaceStyle {
+    pub weight: i32,
+    pub width: i32,
+    #[napi(ts_type = "'upright' | 'italic' | 'oblique'")]
+    pub slant: String,
+}
+
+#[napi(object)]
+pub struct FontFamily {
+    pub family: String,
+    pub registered: bool,
+    pub styles: Vec<TypefaceStyle>,
+    #[napi(ts_type = "Array<'latin' | 'greek' | 'cyrillic' | 'hiragana' | 'katakana' | 'kanji' | 'hangul'>")]
+    pub scripts: Vec<String>,
+}
+
+#[napi(object)]
+pub struct ContrastReport {
+    pub outline_color: String,
+    pub text_background: Option<f64>,
+    pub outline_text: Option<f64>,
+    pub outline_background: Option<f64>,
+    pub sufficient: bool,
+}
+
+#[napi(object)]
+pub struct CropRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[napi(object)]
+pub struct EmojiOutput {
+    pub data: Buffer,
+    pub crop_rect: Option<CropRect>,
+    #[napi(ts_type = "'png' | 'jpeg'")]
+    pub format: String,
+    pub adjustments: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl From<EmojiOptions> for Options {
+    fn from(options: EmojiOptions) -> Self {
+        Self {
+            width: options.width,
+            height: options.height,
+            flexible_width: options.flexible_width,
+            color: options.color,
+            color_palette: options.color_palette,
+            fill_image: options.fill_image.map(|image| image.to_vec()),
+            fill_image_tile_mode: options.fill_image_tile_mode,
+            fill_image_scale: options.fill_image_scale.map(|scale| scale as f32),
+            background_color: options.background_color,
+            text_align: options.text_align,
+            text_size_fixed: options.text_size_fixed,
+            disable_stretch: options.disable_stretch,
+            disable_outline: options.disable_outline,
+            outline_width: options.outline_width,
+            outline_color: options.outline_color,
+            shape: options.shape,
+            shape_color: options.shape_color,
+            shape_border_width: options.shape_border_width,
+            shape_border_color: options.shape_border_color,
+            shape_radius: options.shape_radius,
+            shape_padding: options.shape_padding,
+            background_image: options.background_image.map(|image| image.to_vec()),
+            background_image_fit: options.background_image_fit,
+            overlay_images: options.overlay_images.map(|overlays| overlays.into_iter().map(|overlay| emoji::OverlayImage {
+                image: overlay.image.to_vec(),
+                x: overlay.x,
+                y: overlay.y,
+                width: overlay.width,
+                height: overlay.height,
+            }).collect()),
+            trim: options.trim,
+            trim_padding: options.trim_padding,
+            typeface_file: options.typeface_file,
+            typeface_index: options.typeface_index,
+            typeface_name: options.typeface_name,
+            typeface_weight: options.typeface_weight,
+            typeface_width: options.typeface_width,
+            typeface_slant: options.typeface_slant,
+            typeface_variations: options.typeface_variations.map(|variations| variations.into_iter().map(|(axis, value)| (axis, value as f32)).collect()),
+            fit_width_axis: options.fit_width_axis,
+            opentype_features: options.opentype_features,
+            letter_spacing: options.letter_spacing.map(|letter_spacing| match letter_spacing {
+                Either::A(spacing) => spacing.to_string(),
+                Either::B(spacing) => spacing,
+            }),
+            fit_precision: options.fit_precision.map(|fit_precision| fit_precision as f32),
+            font_hinting: options.font_hinting,
+            font_edging: options.font_edging,
+            font_subpixel: options.font_subpixel,
+            supersample: options.supersample,
+            pixel_art: options.pixel_art,
+            palette_size: options.palette_size,
+            max_bytes: options.max_bytes,
+            format: options.format,
+            matte_color: options.matte_color,
+            alpha_loss: options.alpha_loss,
+            quality: options.quality,
+        }
+    }
+}
+
+// JavaScript側ではエラーのcodeプロパティにエラーコードが入る
+fn to_js_error(error: EmojiError) -> Error<&'static str> {
+    return Error::new(error.code(), error.to_string());
+}
+
+#[napi]
+pub fn generate(text: String, options: Option<EmojiOptions>) -> Result<Buffer, Error<&'static str>> {
+    let result = emoji::generate(text, options.map(Options::from).unwrap_or_default());
+    if result.is_err() {
+        return Err(to_js_error(result.unwrap_err()));
+    }
+
+    return Ok(Buffer::from(result.unwrap()));
+}
+
+#[napi]
+pub fn generate_with_info(text: String, options: Option<EmojiOptions>) -> Result<EmojiOutput, Error<&'static str>> {
+    let result = emoji::generate_with_info(text, options.map(Options::from).unwrap_or_default());
+    if result.is_err() {
+        return Err(to_js_error(result.unwrap_err()));
+    }
+
+    let output = result.unwrap();
+    return Ok(EmojiOutput {
+        data: Buffer::from(output.data),
+        crop_rect: output.crop_rect.map(|rect| CropRect {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }),
+        format: output.format,
+        adjustments: output.adjustments,
+        warnings: output.warnings,
+    });
+}
+
+#[napi]
+pub fn list_typeface_faces(path: String) -> Result<Vec<TypefaceFace>, Error<&'static str>> {
+    let result = emoji::list_faces(path);
+    if result.is_err() {
+        return Err(to_js_error(result.unwrap_err()));
+    }
+
+    return Ok(result.unwrap().into_iter().map(|face| TypefaceFace {
+        index: face.index as u32,
+        family: face.family,
+        weight: face.weight,
+        width: face.width,
+        slant: face.slant,
+    }).collect());
+}
+
+#[napi]
+pub fn register_font(alias: String, source: Either<String, Buffer>, index: Option<u32>) -> Result<(), Error<&'static str>> {
+    let index = index.unwrap_or(0) as usize;
+    let result = match source {
+        Either::A(path) => emoji::register_typeface_file(alias, path, index),
+        Either::B(buffer) => emoji::register_typeface_data(alias, buffer.as_ref(), index),
+    };
+
+    if result.is_err() {
+        return Err(to_js_error(result.unwrap_err()));
+    }
+
+    return Ok(());
+}
+
+#[napi]
+pub fn list_fonts(text: Option<String>) -> Vec<FontFamily> {
+    return emoji::list_fonts(text).into_iter().map(|font| FontFamily {
+        family: font.family,
+        registered: font.registered,
+        styles: font.styles.into_iter().map(|style| TypefaceStyle {
+            weight: style.weight,
+            width: style.width,
+            slant: style.slant,
+        }).collect(),
+        scripts: font.scripts,
+    }).collect();
+}
+
+#[napi]
+pub fn check_contrast(text: String, options: Option<EmojiOptions>) -> Result<ContrastReport, Error<&'static str>> {
+    let result = emoji::check_contrast(text, options.map(Options::from).unwrap_or_default());
+    if result.is_err() {
+        return Err(to_js_error(result.unwrap_err()));
+    }
+
+    let report = result.unwrap();
+    return Ok(ContrastReport {
+        outline_color: report.outline_color,
+        text_background: report.text_background.map(|ratio| ratio as f64),
+        outline_text: report.outline_text.map(|ratio| ratio as f64),
+        outline_background: report.outline_background.map(|ratio| ratio as f64),
+        sufficient: report.sufficient,
+    });
+}
@@ -0,0 +1,224 @@
+use crate::emoji;
+use crate::emoji::Error as EmojiError;
+use crate::emoji::{Options, OverlayImage};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use std::collections::HashMap;
+
+create_exception!(emoji_js, Error, PyException, "Base class of the errors raised by emoji_js. `code` has the error code.");
+create_exception!(emoji_js, InvalidColor, Error);
+create_exception!(emoji_js, InvalidTextAlign, Error);
+create_exception!(emoji_js, InvalidFormat, Error);
+create_exception!(emoji_js, InvalidOption, Error);
+create_exception!(emoji_js, FontLoadFailed, Error);
+create_exception!(emoji_js, SurfaceCreationFailed, Error);
+create_exception!(emoji_js, EncodeFailed, Error);
+create_exception!(emoji_js, LimitsExceeded, Error);
+create_exception!(emoji_js, ImageDecodeFailed, Error);
+create_exception!(emoji_js, AlphaLoss, Error);
+
+// Node.js側と同じエラーコードを持つ例外に変換する
+fn to_py_error(py: Python, error: EmojiError) -> PyErr {
+    let message = error.to_string();
+    let err = match error {
+        EmojiError::InvalidColor(_) => InvalidColor::new_err(message),
+        EmojiError::InvalidTextAlign(_) => InvalidTextAlign::new_err(message),
+        EmojiError::InvalidFormat(_) => InvalidFormat::new_err(message),
+        EmojiError::InvalidOption(_) => InvalidOption::new_err(message),
+        EmojiError::FontLoadFailed(_) => FontLoadFailed::new_err(message),
+        EmojiError::SurfaceCreationFailed => SurfaceCreationFailed::new_err(message),
+        EmojiError::EncodeFailed => EncodeFailed::new_err(message),
+        EmojiError::LimitsExceeded(_) => LimitsExceeded::new_err(message),
+        EmojiError::ImageDecodeFailed(_) => ImageDecodeFailed::new_err(message),
+        EmojiError::AlphaLoss(_) => AlphaLoss::new_err(message),
+    };
+
+    let _ = err.value(py).setattr("code", error.code());
+    return err;
+}
+
+fn extract<'a, T: FromPyObject<'a>>(key: &str, value: &'a PyAny) -> PyResult<Option<T>> {
+    if value.is_none() {
+        return Ok(None);
+    }
+
+    let result = value.extract::<T>();
+    if result.is_err() {
+        return Err(PyTypeError::new_err(format!("Invalid type for {}: {}", key, value.get_type().name()?)));
+    }
+
+    return Ok(Some(result.unwrap()));
+}
+
+// 重ねる画像はimage・x・y・width・heightを持つdictのリストで受け取る
+fn extract_overlays(value: &PyAny) -> PyResult<Option<Vec<OverlayImage>>> {
+    let list = extract::<Vec<&PyDict>>("overlay_images", value)?;
+    if list.is_none() {
+        return Ok(None);
+    }
+
+    let mut overlays = Vec::new();
+    for dict in list.unwrap() {
+        let mut overlay = OverlayImage::default();
+        for (key, value) in dict {
+            let key = key.extract::<String>()?;
+            match key.as_str() {
+                "image" => overlay.image = extract(&key, value)?.unwrap_or_default(),
+                "x" => overlay.x = extract(&key, value)?.unwrap_or_default(),
+                "y" => overlay.y = extract(&key, value)?.unwrap_or_default(),
+                "width" => overlay.width = extract(&key, value)?,
+                "height" => overlay.height = extract(&key, value)?,
+                _ => return Err(PyTypeError::new_err(format!("Unknown key in overlay_images: '{}'", key))),
+            }
+        }
+        overlays.push(overlay);
+    }
+
+    return Ok(Some(overlays));
+}
+
+// キーワード引数をオプションに変換する (名前はNode.js側のオプションのsnake_case)
+fn to_options(kwargs: Option<&PyDict>) -> PyResult<Options> {
+    let mut options = Options::default();
+    if kwargs.is_none() {
+        return Ok(options);
+    }
+
+    for (key, value) in kwargs.unwrap() {
+        let key = key.extract::<String>()?;
+        match key.as_str() {
+            "width" => options.width = extract(&key, value)?,
+            "height" => options.height = extract(&key, value)?,
+            "flexible_width" => options.flexible_width = extract(&key, value)?,
+            "color" => options.color = extract(&key, value)?,
+            "color_palette" => options.color_palette = extract(&key, value)?,
+            "fill_image" => options.fill_image = extract(&key, value)?,
+            "fill_image_tile_mode" => options.fill_image_tile_mode = extract(&key, value)?,
+            "fill_image_scale" => options.fill_image_scale = extract(&key, value)?,
+            "background_color" => options.background_color = extract(&key, value)?,
+            "text_align" => options.text_align = extract(&key, value)?,
+            "text_size_fixed" => options.text_size_fixed = extract(&key, value)?,
+            "disable_stretch" => options.disable_stretch = extract(&key, value)?,
+            "disable_outline" => options.disable_outline = extract(&key, value)?,
+            "outline_width" => options.outline_width = extract(&key, value)?,
+            "outline_color" => options.outline_color = extract(&key, value)?,
+            "shape" => options.shape = extract(&key, value)?,
+            "shape_color" => options.shape_color = extract(&key, value)?,
+            "shape_border_width" => options.shape_border_width = extract(&key, value)?,
+            "shape_border_color" => options.shape_border_color = extract(&key, value)?,
+            "shape_radius" => options.shape_radius = extract(&key, value)?,
+            "shape_padding" => options.shape_padding = extract(&key, value)?,
+            "background_image" => options.background_image = extract(&key, value)?,
+            "background_image_fit" => options.background_image_fit = extract(&key, value)?,
+            "overlay_images" => options.overlay_images = extract_overlays(value)?,
+            "trim" => options.trim = extract(&key, value)?,
+            "trim_padding" => options.trim_padding = extract(&key, value)?,
+            "typeface_file" => options.typeface_file = extract(&key, value)?,
+            "typeface_index" => options.typeface_index = extract(&key, value)?,
+            "typeface_name" => options.typeface_name = extract(&key, value)?,
+            "typeface_weight" => options.typeface_weight = extract(&key, value)?,
+            "typeface_width" => options.typeface_width = extract(&key, value)?,
+            "typeface_slant" => options.typeface_slant = extract(&key, value)?,
+            "typeface_variations" => options.typeface_variations = extract::<HashMap<String, f32>>(&key, value)?,
+            "fit_width_axis" => options.fit_width_axis = extract(&key, value)?,
+            "opentype_features" => options.opentype_features = extract::<HashMap<String, u32>>(&key, value)?,
+            "letter_spacing" => {
+                // 数値ならpx、文字列なら単位付きとして扱う
+                options.letter_spacing = match extract::<f64>(&key, value) {
+                    Ok(spacing) => spacing.map(|spacing| spacing.to_string()),
+                    Err(_) => extract::<String>(&key, value)?,
+                };
+            },
+            "fit_precision" => options.fit_precision = extract(&key, value)?,
+            "font_hinting" => options.font_hinting = extract(&key, value)?,
+            "font_edging" => options.font_edging = extract(&key, value)?,
+            "font_subpixel" => options.font_subpixel = extract(&key, value)?,
+            "supersample" => options.supersample = extract(&key, value)?,
+            "pixel_art" => options.pixel_art = extract(&key, value)?,
+            "palette_size" => options.palette_size = extract(&key, value)?,
+            "max_bytes" => options.max_bytes = extract(&key, value)?,
+            "format" => options.format = extract(&key, value)?,
+            "matte_color" => options.matte_color = extract(&key, value)?,
+            "alpha_loss" => options.alpha_loss = extract(&key, value)?,
+            "quality" => options.quality = extract(&key, value)?,
+            _ => return Err(PyTypeError::new_err(format!("generate() got an unexpected keyword argument '{}'", key))),
+        }
+    }
+
+    return Ok(options);
+}
+
+/// generate(text, **options) -> bytes
+///
+/// Generates an emoji image from the text and returns the encoded image.
+#[pyfunction]
+#[pyo3(signature = (text, **options))]
+fn generate(py: Python, text: String, options: Option<&PyDict>) -> PyResult<Py<PyBytes>> {
+    let options = to_options(options)?;
+
+    // 生成中はGILを解放して他のスレッドを止めないようにする
+    let result = py.allow_threads(|| emoji::generate(text, options));
+    if result.is_err() {
+        return Err(to_py_error(py, result.unwrap_err()));
+    }
+
+    return Ok(PyBytes::new(py, &result.unwrap()).into());
+}
+
+/// generate_with_info(text, **options) -> dict
+///
+/// Same as generate(), but returns a dict with the image in `data`, the rectangle
+/// cropped by `trim` (a dict with x, y, width and height, or None) in `crop_rect`,
+/// the image format in `format`, the adjustments made for `max_bytes` in `adjustments`
+/// and the warnings (eg. with `alpha_loss='warn'`) in `warnings`.
+#[pyfunction]
+#[pyo3(signature = (text, **options))]
+fn generate_with_info(py: Python, text: String, options: Option<&PyDict>) -> PyResult<Py<PyDict>> {
+    let options = to_options(options)?;
+
+    let result = py.allow_threads(|| emoji::generate_with_info(text, options));
+    if result.is_err() {
+        return Err(to_py_error(py, result.unwrap_err()));
+    }
+
+    let output = result.unwrap();
+    let dict = PyDict::new(py);
+    dict.set_item("data", PyBytes::new(py, &output.data))?;
+    match output.crop_rect {
+        Some(rect) => {
+            let crop_rect = PyDict::new(py);
+            crop_rect.set_item("x", rect.x)?;
+            crop_rect.set_item("y", rect.y)?;
+            crop_rect.set_item("width", rect.width)?;
+            crop_rect.set_item("height", rect.height)?;
+            dict.set_item("crop_rect", crop_rect)?;
+        },
+        None => dict.set_item("crop_rect", py.None())?,
+    }
+    dict.set_item("format", output.format)?;
+    dict.set_item("adjustments", output.adjustments)?;
+    dict.set_item("warnings", output.warnings)?;
+
+    return Ok(dict.into());
+}
+
+#[pymodule]
+#[pyo3(name = "emoji_js")]
+fn module(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(generate, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_with_info, m)?)?;
+    m.add("Error", py.get_type::<Error>())?;
+    m.add("InvalidColor", py.get_type::<InvalidColor>())?;
+    m.add("InvalidTextAlign", py.get_type::<InvalidTextAlign>())?;
+    m.add("InvalidFormat", py.get_type::<InvalidFormat>())?;
+    m.add("InvalidOption", py.get_type::<InvalidOption>())?;
+    m.add("FontLoadFailed", py.get_type::<FontLoadFailed>())?;
+    m.add("SurfaceCreationFailed", py.get_type::<SurfaceCreationFailed>())?;
+    m.add("EncodeFailed", py.get_type::<EncodeFailed>())?;
+    m.add("LimitsExceeded", py.get_type::<LimitsExceeded>())?;
+    m.add("ImageDecodeFailed", py.get_type::<ImageDecodeFailed>())?;
+    m.add("AlphaLoss", py.get_type::<AlphaLoss>())?;
+    return Ok(());
+}
@@ -1,4 +1,5 @@
-import { generate } from '..'
+import { readFileSync } from 'fs'
+import { checkContrast, generate, generateWithInfo, listFonts, listTypefaceFaces, registerFont } from '..'
 
 describe('basic test', () => {
     // バイナリが返される
@@ -11,6 +12,84 @@ describe('basic test', () => {
         expect(() => generate('emoji', { color: 'wrong color' })).toThrowError()
     })
 
+    // CSSの色の構文で色を指定できる
+    it('accept css color syntax', () => {
+        const hex = generate('emoji', { color: '#FF0000', outlineColor: '#0000FF80' })
+        expect(generate('emoji', { color: '#f00f', outlineColor: 'rgba(0, 0, 255, 50%)' })).toEqual(hex)
+        expect(generate('emoji', { color: 'red', outlineColor: 'rgb(0 0 255 / 50%)' })).toEqual(hex)
+        expect(generate('emoji', { color: 'hsl(0, 100%, 50%)', outlineColor: 'rgba(0, 0, 255, 0.5)' })).toEqual(hex)
+        expect(generate('emoji', { color: 'hwb(0 0% 0%)', outlineColor: 'hsl(240deg 100% 50% / 0.5)' })).toEqual(hex)
+        expect(() => generate('emoji', { color: 'rgb(255 0)' })).toThrowError()
+    })
+
+    // outlineColorにautoを指定すると文字色と背景色から縁取りの色が決まる
+    it('choose outline color automatically', () => {
+        expect(checkContrast('emoji', { color: '#000000', outlineColor: 'auto' }).outlineColor).toBe('#FFFFFFFF')
+        expect(checkContrast('emoji', { color: '#FFFFFF', outlineColor: 'auto' }).outlineColor).toBe('#000000FF')
+
+        const report = checkContrast('emoji', { color: '#202020', outlineColor: 'auto', backgroundColor: '#FFFFFF' })
+        expect(report.outlineColor).toBe('#7E7E7EFF')
+        expect(report.sufficient).toBe(true)
+
+        // 塗りのあるシェイプではシェイプの塗りを背景として扱い、矩形は背景色のまま扱う
+        const shape = checkContrast('emoji', { color: '#202020', outlineColor: 'auto', shape: 'circle', shapeColor: '#FFFFFF' })
+        expect(shape.outlineColor).toBe('#7E7E7EFF')
+        expect(shape.textBackground).toBeDefined()
+
+        const rect = checkContrast('emoji', { color: '#202020', outlineColor: 'auto', shape: 'rect', shapeColor: '#FFFFFF', shapePadding: 4 })
+        expect(rect.outlineColor).toBe('#FFFFFFFF')
+        expect(rect.textBackground).toBeUndefined()
+    })
+
+    // colorにhashを指定するとテキストから色が決まる
+    it('derive color from text', () => {
+        expect(generate('emoji', { color: 'hash' })).toEqual(generate('emoji', { color: 'hash' }))
+        expect(generate('emoji', { color: 'hash' })).not.toEqual(generate('emoji', { color: '#000000' }))
+
+        const palette = ['#FF0000', '#00FF00', '#0000FF']
+        const outlineColor = checkContrast('emoji', { color: 'hash', colorPalette: palette, outlineColor: 'hash' }).outlineColor
+        expect(['#FF0000FF', '#00FF00FF', '#0000FFFF']).toContain(outlineColor)
+
+        // パレットが1色だけなら文字色と同じにならないようautoの色になる
+        const single = checkContrast('emoji', { color: 'hash', colorPalette: ['#000000'], outlineColor: 'hash' }).outlineColor
+        expect(single).toBe('#FFFFFFFF')
+        expect(() => generate('emoji', { color: 'hash', colorPalette: ['wrong color'] })).toThrowError()
+    })
+
+    // コントラストが足りない組み合わせを検出できる
+    it('report insufficient contrast', () => {
+        const report = checkContrast('emoji', { color: '#000000', outlineColor: '#111111' })
+        expect(report.outlineText).toBeLessThan(3)
+        expect(report.textBackground).toBeUndefined()
+        expect(report.sufficient).toBe(false)
+    })
+
+    // 間違ったshapeを指定するとエラーになる
+    it('throw error when wrong shape', () => {
+        expect(() => generate('emoji', { shape: 'wrong shape' as any })).toThrowError()
+    })
+
+    // 画像として読めないデータを背景画像に指定するとエラーになる
+    it('throw error when wrong background image', () => {
+        expect(() => generate('emoji', { backgroundImage: Buffer.from('wrong image') })).toThrowError()
+        expect(() => generate('emoji', { overlayImages: [{ image: Buffer.from('wrong image'), x: 0, y: 0 }] })).toThrowError()
+        expect(() => generate('emoji', { fillImage: Buffer.from('wrong image') })).toThrowError()
+    })
+
+    // エラーにはエラーコードが含まれる
+    it('throw error with code', () => {
+        const codeOf = (fn: () => unknown) => {
+            try {
+                fn()
+            } catch (e: any) {
+                return e.code
+            }
+        }
+
+        expect(codeOf(() => generate('emoji', { color: 'wrong color' }))).toBe('InvalidColor')
+        expect(codeOf(() => generate('emoji', { width: 100000 }))).toBe('LimitsExceeded')
+    })
+
     // 間違ったtextAlignを指定するとエラーになる
     it('throw error when wrong textAlign', () => {
         expect(() => generate('emoji', { textAlign: 'wrong textAlign' as any })).toThrowError()
@@ -20,6 +99,192 @@ describe('basic test', () => {
     it('throw error when wrong format', () => {
         expect(() => generate('emoji', { format: 'wrong format' as any })).toThrowError()
     })
+
+    // 間違ったalphaLossを指定するとエラーになる
+    it('throw error when wrong alphaLoss', () => {
+        expect(() => generate('emoji', { alphaLoss: 'wrong alphaLoss' as any })).toThrowError()
+        expect(() => generate('emoji', { matteColor: 'wrong color' })).toThrowError()
+    })
+
+    // 透明な部分があるとき、alphaLossがerrorならjpegではエラーになる
+    it('throw error when alpha is lost', () => {
+        expect(() => generate('emoji', { format: 'jpeg', alphaLoss: 'error' })).toThrowError()
+        expect(() => generate('emoji', { format: 'jpeg', alphaLoss: 'error', backgroundColor: '#FFFFFF' })).not.toThrowError()
+        expect(() => generate('emoji', { format: 'png', alphaLoss: 'error' })).not.toThrowError()
+    })
+
+    // alphaLossがwarnなら警告が返される
+    it('return warning when alpha is lost', () => {
+        expect(generateWithInfo('emoji', { format: 'jpeg', alphaLoss: 'warn' }).warnings.length).toBe(1)
+        expect(generateWithInfo('emoji', { format: 'jpeg', alphaLoss: 'warn', backgroundColor: '#FFFFFF' }).warnings).toEqual([])
+    })
+
+    // 間違ったtrimを指定するとエラーになる
+    it('throw error when wrong trim', () => {
+        expect(() => generate('emoji', { trim: 'wrong trim' as any })).toThrowError()
+    })
+
+    // trimを指定すると透明な余白を切り取った範囲が返される
+    it('return crop rect when trimmed', () => {
+        expect(generateWithInfo('emoji').cropRect).toBeUndefined()
+
+        const { data, cropRect } = generateWithInfo('emoji', { textAlign: 'left', trim: 'bounds' })
+        expect(data).toBeInstanceOf(Buffer)
+        expect(cropRect!.width).toBeLessThanOrEqual(128)
+        expect(cropRect!.height).toBeLessThanOrEqual(128)
+
+        const padded = generateWithInfo('emoji', { textAlign: 'left', trim: 'bounds', trimPadding: 4 }).cropRect!
+        expect(padded.width).toBe(cropRect!.width + 8)
+        expect(padded.x).toBe(cropRect!.x - 4)
+        expect(() => generate('emoji', { trim: 'bounds', trimPadding: 16000 })).toThrowError()
+        expect(() => generate('emoji', { trim: 'bounds', trimPadding: 4294967295 })).toThrowError()
+
+        expect(generateWithInfo('emoji', { trim: 'keep_height' }).cropRect!.height).toBe(128)
+        const aspect = generateWithInfo('emoji', { width: 256, trim: 'keep_aspect' }).cropRect!
+        expect(Math.abs(aspect.width - aspect.height * 2)).toBeLessThanOrEqual(1)
+
+        // 背景が不透明な場合は切り取られない
+        expect(generateWithInfo('emoji', { backgroundColor: '#FFFFFF', trim: 'bounds' }).cropRect).toEqual({ x: 0, y: 0, width: 128, height: 128 })
+    })
+
+    // 間違ったtypefaceWidthを指定するとエラーになる
+    it('throw error when wrong typefaceWidth', () => {
+        expect(() => generate('emoji', { typefaceWidth: 'wrong width' as any })).toThrowError()
+    })
+
+    // 間違ったバリエーション軸を指定するとエラーになる
+    it('throw error when wrong typefaceVariations', () => {
+        expect(() => generate('emoji', { typefaceVariations: { weight: 900 } })).toThrowError()
+    })
+
+    // 間違ったOpenTypeフィーチャーを指定するとエラーになる
+    it('throw error when wrong opentypeFeatures', () => {
+        expect(() => generate('emoji', { opentypeFeatures: { palette: 1 } })).toThrowError()
+    })
+
+    // 間違った字間を指定するとエラーになる
+    it('throw error when wrong letterSpacing', () => {
+        expect(() => generate('emoji', { letterSpacing: 'wide' })).toThrowError()
+        expect(() => generate('emoji', { letterSpacing: 'NaN' })).toThrowError()
+        expect(() => generate('emoji', { letterSpacing: 'inf' })).toThrowError()
+        expect(() => generate('emoji', { letterSpacing: '1e40em' })).toThrowError()
+    })
+
+    // 字間を指定すると描画結果が変わる
+    it('change output with letterSpacing', () => {
+        const options = { width: 256, height: 64, textSizeFixed: true, disableStretch: true }
+        expect(generate('emoji', { ...options, letterSpacing: '0.2em' })).not.toEqual(generate('emoji', options))
+        expect(generate('emoji', { ...options, letterSpacing: '4px' })).toEqual(generate('emoji', { ...options, letterSpacing: '4' }))
+    })
+
+    // opentypeFeaturesで合字を無効にすると描画結果が変わる
+    it('change output with opentypeFeatures', () => {
+        const options = { typefaceFile: 'test/assets/DejaVu.ttc', typefaceIndex: 1 }
+        expect(generate('fifl', { ...options, opentypeFeatures: { liga: 0 } })).not.toEqual(generate('fifl', { ...options, opentypeFeatures: { liga: 1 } }))
+    })
+
+    // 0以下のfitPrecisionを指定するとエラーになる
+    it('throw error when wrong fitPrecision', () => {
+        expect(() => generate('emoji', { fitPrecision: 0 })).toThrowError()
+    })
+
+    // 太い縁取りで幅に収まらなくても、文字が潰れずに描画される
+    it('keep text visible when thick outline overflows', () => {
+        const options = { width: 64, height: 64, outlineWidth: 40 }
+        expect(generate('あいうえおかきくけこ', options)).not.toEqual(generate('さしすせそたちつてと', options))
+    })
+
+    // 間違ったフォントの描画設定を指定するとエラーになる
+    it('throw error when wrong font rendering', () => {
+        expect(() => generate('emoji', { fontHinting: 'wrong hinting' as any })).toThrowError()
+        expect(() => generate('emoji', { fontEdging: 'wrong edging' as any })).toThrowError()
+    })
+
+    // スーパーサンプリングしても出力サイズは変わらない
+    it('keep size when supersampled', () => {
+        const data = generate('emoji', { width: 32, height: 32, supersample: 4 })
+        expect(data.readUInt32BE(16)).toBe(32)
+        expect(data.readUInt32BE(20)).toBe(32)
+        expect(() => generate('emoji', { supersample: 0 })).toThrowError()
+        expect(() => generate('emoji', { supersample: 9 })).toThrowError()
+    })
+
+    // 範囲外のpaletteSizeを指定するとエラーになる
+    it('throw error when wrong paletteSize', () => {
+        expect(() => generate('emoji', { paletteSize: 1 })).toThrowError()
+        expect(() => generate('emoji', { paletteSize: 257 })).toThrowError()
+    })
+
+    // maxBytesに収まるように調整され、行った調整が返される
+    it('fit image within maxBytes', () => {
+        const size = generate('emoji', { backgroundColor: '#FFFFFF' }).length
+        const opaque = generateWithInfo('emoji', { backgroundColor: '#FFFFFF', maxBytes: Math.floor(size / 2) })
+        expect(opaque.data.length).toBeLessThanOrEqual(Math.floor(size / 2))
+        expect(opaque.adjustments.length).toBeGreaterThan(0)
+
+        expect(generateWithInfo('emoji', { maxBytes: size * 2 }).adjustments).toEqual([])
+        expect(() => generate('emoji', { maxBytes: 1 })).toThrowError()
+        expect(() => generate('emoji', { maxBytes: 0 })).toThrowError()
+    })
+
+    // ドット絵モードでは描画結果が変わる
+    it('disable antialiasing in pixel art mode', () => {
+        expect(generate('emoji', { pixelArt: true })).toEqual(generate('emoji', { pixelArt: true, supersample: 4 }))
+        expect(generate('emoji', { pixelArt: true })).not.toEqual(generate('emoji'))
+    })
+
+    // typefaceIndexでコレクション内の別のフェイスが使われる
+    it('select face in collection with typefaceIndex', () => {
+        expect(listTypefaceFaces('test/assets/DejaVu.ttc').map((face) => face.family)).toEqual(['DejaVu Sans Mono', 'DejaVu Serif'])
+
+        const options = { typefaceFile: 'test/assets/DejaVu.ttc' }
+        expect(generate('emoji', { ...options, typefaceIndex: 1 })).not.toEqual(generate('emoji', { ...options, typefaceIndex: 0 }))
+        expect(() => generate('emoji', { ...options, typefaceIndex: 2 })).toThrowError()
+    })
+
+    // 存在しないフォントファイルのフェイス一覧を取得するとエラーになる
+    it('throw error when listing faces of missing file', () => {
+        expect(() => listTypefaceFaces('test/assets/missing.ttc')).toThrowError()
+    })
+
+    // フォント一覧が配列で返される
+    it('return font list', () => {
+        expect(listFonts()).toBeInstanceOf(Array)
+    })
+
+    // 太字・斜体のフェイスが無いフォントでも、typefaceWeightとtypefaceSlantで擬似的に太字・斜体になる
+    it('synthesize bold and italic with typefaceWeight and typefaceSlant', () => {
+        const options = { typefaceFile: 'test/assets/DejaVu.ttc', typefaceIndex: 1 }
+        const regular = generate('emoji', options)
+        const bold = generate('emoji', { ...options, typefaceWeight: 700 })
+        const italic = generate('emoji', { ...options, typefaceSlant: 'italic' })
+        expect(bold).not.toEqual(regular)
+        expect(italic).not.toEqual(regular)
+        expect(italic).not.toEqual(bold)
+
+        // 半太字未満の指定では擬似太字にならない
+        expect(generate('emoji', { ...options, typefaceWeight: 400 })).toEqual(regular)
+    })
+
+    // wdth軸の無いフォントではfitWidthAxisを指定しても横方向の圧縮だけが行われる
+    it('fall back to scale when font has no width axis', () => {
+        const options = { typefaceFile: 'test/assets/DejaVu.ttc', typefaceIndex: 1 }
+        expect(generate('emoji emoji emoji', { ...options, fitWidthAxis: true })).toEqual(generate('emoji emoji emoji', options))
+    })
+
+    // registerFontで登録したフォントをtypefaceNameで使える
+    it('use registered font with typefaceName', () => {
+        registerFont('test-serif', readFileSync('test/assets/DejaVu.ttc'), 1)
+        expect(generate('emoji', { typefaceName: 'test-serif' })).toEqual(generate('emoji', { typefaceFile: 'test/assets/DejaVu.ttc', typefaceIndex: 1 }))
+
+        registerFont('test-mono', 'test/assets/DejaVu.ttc')
+        expect(generate('emoji', { typefaceName: 'test-mono' })).toEqual(generate('emoji', { typefaceFile: 'test/assets/DejaVu.ttc' }))
+    })
+
+    // フォントではないデータを登録するとエラーになる
+    it('throw error when registering invalid font', () => {
+        expect(() => registerFont('invalid', Buffer.from('not a font'))).toThrowError()
+    })
 })
 
 // プラットフォームによってレンダリングされる画像に差があり、問題がない場合でもテストが失敗することがあるためにデフォルトでは実行しない
@@ -44,6 +309,37 @@ describe.skip('binary test', () => {
         expect(generate('emoji', { backgroundColor: '#A3BE8C' })).toMatchImageSnapshot()
     })
 
+    // 背景の図形を指定できる
+    it('generate emoji with background shape', () => {
+        expect(generate('emoji', { shape: 'circle', shapeColor: '#A3BE8C' })).toMatchImageSnapshot()
+        expect(generate('emoji', { shape: 'speech_bubble', shapeColor: '#FFFFFF', shapeBorderWidth: 4, shapePadding: 4 })).toMatchImageSnapshot()
+    })
+
+    // 背景画像と重ねる画像を指定できる
+    it('generate emoji with images', () => {
+        const image = generate('画', { backgroundColor: '#A3BE8C' })
+        expect(generate('emoji', { backgroundImage: image, backgroundImageFit: 'tile', width: 256 })).toMatchImageSnapshot()
+        expect(generate('emoji', { overlayImages: [{ image, x: 96, y: 96, width: 32 }] })).toMatchImageSnapshot()
+    })
+
+    // 画像で文字を塗りつぶせる
+    it('generate emoji with fill image', () => {
+        const texture = generate('柄', { color: '#EBCB8B', backgroundColor: '#BF616A', width: 16, height: 16, disableOutline: true })
+        expect(generate('emoji', { fillImage: texture, fillImageTileMode: 'mirror', fillImageScale: 2 })).toMatchImageSnapshot()
+        expect(() => generate('emoji', { fillImage: texture, fillImageScale: 0 })).toThrowError()
+    })
+
+    // ドット絵モードと色数の制限を指定できる
+    it('generate emoji with pixelArt', () => {
+        expect(generate('絵文字', { width: 32, height: 32, pixelArt: true, paletteSize: 4, typefaceFile: 'test/assets/NotoSansJP-Regular.ttf' })).toMatchImageSnapshot()
+    })
+
+    // 小さいサイズでスーパーサンプリングとフォントの描画設定を指定できる
+    it('generate emoji with supersample', () => {
+        expect(generate('emoji', { width: 32, height: 32, supersample: 4 })).toMatchImageSnapshot()
+        expect(generate('emoji', { width: 32, height: 32, fontHinting: 'full', fontSubpixel: true })).toMatchImageSnapshot()
+    })
+
     // textSizeFixedを指定できる
     it('generate emoji with textSizeFixed', () => {
         expect(generate('emoji\ngen', { textSizeFixed: true })).toMatchImageSnapshot()